@@ -0,0 +1,249 @@
+//! Wait-free single-producer multi-reader buffer to share the latest value with several threads.
+//!
+//! This is the multi-reader counterpart to the [triple_buffer](crate::triple_buffer).
+//! With `N` readers, `N + 2` slots are used: one for each reader, one for the latest value
+//! and one for the writer. Every reader gets the newest published value independently of the others.
+//! It is based on the wait-free protocol of Chen and Burns, where the writer helps pending readers.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::broadcast_buffer;
+//!
+//! let (mut wr, mut readers) = broadcast_buffer::broadcast_buffer(2);
+//! wr.write(42);
+//! for rd in readers.iter_mut() {
+//!     assert_eq!(rd.try_read(), Some(42));
+//! }
+//! ```
+
+use crate::import::{Arc, AtomicUsize, Ordering, UnsafeCell};
+use crossbeam_utils::CachePadded;
+
+/// Marks a reader which is about to claim a slot.
+const PENDING: usize = usize::MAX;
+
+/// The slots of a broadcast buffer. The content `S` of a slot is generic, so it can be reused, e.g. by [rcu](crate::rcu).
+#[derive(Debug)]
+pub(crate) struct Shared<S> {
+    pub(crate) mem: Box<[UnsafeCell<S>]>,
+    latest: CachePadded<AtomicUsize>,
+    // The slot each reader currently uses or [PENDING].
+    reading: Box<[CachePadded<AtomicUsize>]>,
+}
+
+impl<S> Shared<S> {
+    pub(crate) fn new(readers: usize, init: impl FnMut(usize) -> S) -> Self {
+        let mem = (0..readers + 2).map(init).map(UnsafeCell::new).collect();
+        // Every reader starts on the initial latest slot.
+        let reading = (0..readers)
+            .map(|_| CachePadded::new(AtomicUsize::new(0)))
+            .collect();
+        Shared {
+            mem,
+            latest: CachePadded::new(0.into()),
+            reading,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn readers(&self) -> usize {
+        self.reading.len()
+    }
+
+    /// Claims the latest slot for the reader `id` and returns its index.
+    /// The slot stays claimed until the next call.
+    #[inline]
+    pub(crate) fn claim(&self, id: usize) -> usize {
+        // Only read-modify-write operations are used on `latest` and `reading`, because they always
+        // see the newest value. So either the reader sees the latest slot, or the writer sees the pending reader.
+        let reading = &self.reading[id];
+        reading.swap(PENDING, Ordering::AcqRel);
+        let latest = self.latest.fetch_add(0, Ordering::AcqRel);
+        // If the writer published in between, it already claimed its newest slot for us.
+        match reading.compare_exchange(PENDING, latest, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => latest,
+            Err(claimed) => claimed,
+        }
+    }
+
+    /// Returns the index of a slot which is neither the latest one nor claimed by a reader.
+    /// The `in_use` scratch memory must have one entry per slot.
+    #[inline]
+    pub(crate) fn free_slot(&self, in_use: &mut [bool]) -> usize {
+        in_use.fill(false);
+        // Only the writer changes `latest`.
+        in_use[self.latest.load(Ordering::Relaxed)] = true;
+        for reading in self.reading.iter() {
+            let idx = reading.fetch_add(0, Ordering::AcqRel);
+            if idx != PENDING {
+                in_use[idx] = true;
+            }
+        }
+        // There are `readers + 2` slots, so at least one is always free.
+        in_use.iter().position(|used| !used).unwrap()
+    }
+
+    /// Publishes the slot as the latest one.
+    #[inline]
+    pub(crate) fn publish(&self, idx: usize) {
+        self.latest.swap(idx, Ordering::AcqRel);
+        // Help readers which are about to claim a slot.
+        for reading in self.reading.iter() {
+            let _ = reading.compare_exchange(PENDING, idx, Ordering::AcqRel, Ordering::Acquire);
+        }
+    }
+}
+
+/// Create a new wait-free broadcast buffer with a fixed number of readers.
+/// # Example
+/// ```rust
+/// use waitfree_sync::broadcast_buffer;
+///
+/// //               Data type ──╮        ╭─ Number of readers
+/// let (wr, readers) = broadcast_buffer::broadcast_buffer::<u64>(3);
+/// assert_eq!(readers.len(), 3);
+/// ```
+pub fn broadcast_buffer<T>(readers: usize) -> (Writer<T>, Vec<Reader<T>>) {
+    let chan = Arc::new(Shared::new(readers, |_| None));
+
+    let r = (0..readers)
+        .map(|id| Reader::new(chan.clone(), id))
+        .collect();
+    let w = Writer::new(chan);
+    (w, r)
+}
+
+/// One of the reading sides of the [broadcast_buffer].
+#[derive(Debug)]
+pub struct Reader<T> {
+    shared: Arc<Shared<Option<T>>>,
+    id: usize,
+}
+unsafe impl<T: Send> Send for Reader<T> {}
+unsafe impl<T: Send> Sync for Reader<T> {}
+
+impl<T> Reader<T> {
+    fn new(shared: Arc<Shared<Option<T>>>, id: usize) -> Self {
+        Reader { shared, id }
+    }
+
+    /// Reads the latest available value.
+    /// Returns [None] if the [Writer] has not written anything yet.
+    #[inline]
+    pub fn try_read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let read_idx = self.shared.claim(self.id);
+
+        #[cfg(loom)]
+        let val = unsafe { self.shared.mem[read_idx].get().deref() }.clone();
+        #[cfg(not(loom))]
+        let val = unsafe { &*self.shared.mem[read_idx].get() }.clone();
+        val
+    }
+}
+
+/// The writing side of the [broadcast_buffer].
+#[derive(Debug)]
+pub struct Writer<T> {
+    shared: Arc<Shared<Option<T>>>,
+    // Scratch memory to find a free slot without allocation.
+    in_use: Box<[bool]>,
+    last_written: Option<usize>,
+}
+unsafe impl<T: Send> Send for Writer<T> {}
+unsafe impl<T: Send> Sync for Writer<T> {}
+
+impl<T> Writer<T> {
+    fn new(shared: Arc<Shared<Option<T>>>) -> Self {
+        let in_use = vec![false; shared.mem.len()].into_boxed_slice();
+        Writer {
+            shared,
+            in_use,
+            last_written: None,
+        }
+    }
+
+    /// Returns the number of readers.
+    #[inline]
+    pub fn readers(&self) -> usize {
+        self.shared.readers()
+    }
+
+    /// Reads the latest available value.
+    /// Returns [None] if the [Writer] has not written anything yet.
+    #[inline]
+    pub fn try_read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let last_written = self.last_written?;
+
+        #[cfg(loom)]
+        let val = unsafe { self.shared.mem[last_written].get().deref() }.clone();
+        #[cfg(not(loom))]
+        let val = unsafe { &*self.shared.mem[last_written].get() }.clone();
+        val
+    }
+
+    /// Writes an new value.
+    #[inline]
+    pub fn write(&mut self, data: T) {
+        let write_idx = self.shared.free_slot(&mut self.in_use);
+
+        #[cfg(loom)]
+        unsafe {
+            self.shared.mem[write_idx].get_mut().with(|ptr| {
+                let _ = ptr.replace(Some(data));
+            });
+        }
+        #[cfg(not(loom))]
+        // Drop old value and write new one
+        let _ = unsafe { self.shared.mem[write_idx].get().replace(Some(data)) };
+
+        self.last_written = Some(write_idx);
+        self.shared.publish(write_idx);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = broadcast_buffer(3);
+        w.write(vec![0; 15]);
+
+        assert_eq!(w.try_read(), Some(vec![0; 15]));
+        for r in r.iter_mut() {
+            assert_eq!(r.try_read(), Some(vec![0; 15]));
+        }
+    }
+
+    #[test]
+    fn test_read_none() {
+        let (mut w, mut r) = broadcast_buffer(2);
+        assert_eq!(r[0].try_read(), None);
+        w.write(1);
+        assert_eq!(r[0].try_read(), Some(1));
+        assert_eq!(r[1].try_read(), Some(1));
+    }
+
+    #[test]
+    fn test_independent_readers() {
+        let (mut w, mut r) = broadcast_buffer(2);
+        w.write(1);
+        assert_eq!(r[0].try_read(), Some(1));
+        for i in 2..10 {
+            w.write(i);
+        }
+        assert_eq!(r[0].try_read(), Some(9));
+        assert_eq!(r[1].try_read(), Some(9));
+        w.write(10);
+        assert_eq!(r[1].try_read(), Some(10));
+        assert_eq!(r[1].try_read(), Some(10));
+        assert_eq!(r[0].try_read(), Some(10));
+    }
+}
@@ -0,0 +1,396 @@
+//! A wait-free single-producer multi-consumer broadcast ring, where every [Receiver] sees every message.
+//!
+//! The messages are kept in one shared slot array and every [Receiver] has its own read cursor,
+//! similar to the LMAX Disruptor. Therefore, the messages are not copied into a queue per receiver.
+//! If the ring is full, the [Sender] either rejects new messages until the slowest receiver caught up
+//! ([Overflow::Reject]) or overwrites the oldest message ([Overflow::Overwrite]).
+//! In the latter case, a lagging receiver detects the overrun with a [LaggedError].
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::broadcast_ring::{self, Overflow};
+//!
+//! //                       Type ──╮  Capacity ─╮  ╭─ Number of receivers
+//! let (mut tx, mut rxs) = broadcast_ring::broadcast_ring::<u64>(8, 2, Overflow::Reject);
+//! tx.try_send(234).unwrap();
+//! assert_eq!(rxs[0].try_recv(), Ok(Some(234)));
+//! assert_eq!(rxs[1].try_recv(), Ok(Some(234)));
+//! ```
+//!
+//! # Behavior for full and empty ring.
+//! With [Overflow::Reject], the [Sender] returns a [NoSpaceLeftError] if the slowest receiver has not read the oldest message yet.
+//! With [Overflow::Overwrite], it only does so while a receiver is reading the oldest message right now.
+//! If a receiver has read all messages, it returns `Ok(None)`.
+
+use crate::import::{Arc, AtomicUsize, Ordering, UnsafeCell};
+use crate::spsc::is_power_of_two;
+pub use crate::spsc::NoSpaceLeftError;
+use core::error::Error;
+use crossbeam_utils::CachePadded;
+
+/// Set in the state of a slot while the sender overwrites it. The remaining bits count the readers.
+const WRITING: usize = 1 << (usize::BITS - 1);
+
+/// Create a new wait-free broadcast ring. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::broadcast_ring::{self, Overflow};
+///
+/// //             Data type ──╮  Capacity ─╮  ╭─ Number of receivers
+/// let (tx, rxs) = broadcast_ring::broadcast_ring::<u64>(8, 3, Overflow::Overwrite);
+/// ```
+pub fn broadcast_ring<T>(
+    capacity: usize,
+    receivers: usize,
+    overflow: Overflow,
+) -> (Sender<T>, Vec<Receiver<T>>) {
+    if !is_power_of_two(capacity) {
+        panic!("The SIZE must be a power of 2")
+    }
+
+    let chan = Arc::new(Ring::new(capacity, receivers, overflow));
+
+    let r = (0..receivers)
+        .map(|id| Receiver::new(chan.clone(), id))
+        .collect();
+    let w = Sender::new(chan);
+
+    (w, r)
+}
+
+/// Defines what the [Sender] does if the ring is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Reject new messages until the slowest receiver has read the oldest message.
+    Reject,
+    /// Overwrite the oldest message. Lagging receivers return a [LaggedError].
+    Overwrite,
+}
+
+/// Indicates that the receiver lagged behind and messages were overwritten.
+/// The receiver continues with the oldest available message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaggedError(pub usize);
+impl Error for LaggedError {}
+impl core::fmt::Display for LaggedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The receiver lagged behind and missed {} messages.",
+            self.0
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    value: UnsafeCell<Option<T>>,
+    // The position of the message in this slot.
+    seq: AtomicUsize,
+    // Only used with [Overflow::Overwrite].
+    state: CachePadded<AtomicUsize>,
+}
+
+#[derive(Debug)]
+struct Ring<T> {
+    mem: Box<[Slot<T>]>,
+    // Position of the next message of the sender.
+    published: CachePadded<AtomicUsize>,
+    // Position of the next message of every receiver. Only used with [Overflow::Reject].
+    cursors: Box<[CachePadded<AtomicUsize>]>,
+    overflow: Overflow,
+    // The mask is written when this structure is created and is then only read.
+    // Therefore, we do not need Atomic here.
+    mask: usize,
+}
+
+impl<T> Ring<T> {
+    fn new(size: usize, receivers: usize, overflow: Overflow) -> Self {
+        let mut buffer = Vec::with_capacity(size);
+        for _ in 0..size {
+            buffer.push(Slot {
+                value: UnsafeCell::new(None),
+                seq: AtomicUsize::new(usize::MAX),
+                state: CachePadded::new(0.into()),
+            });
+        }
+        let buffer: Box<[Slot<T>]> = buffer.into_boxed_slice();
+        Ring {
+            mem: buffer,
+            published: CachePadded::new(0.into()),
+            cursors: (0..receivers)
+                .map(|_| CachePadded::new(AtomicUsize::new(0)))
+                .collect(),
+            overflow,
+            mask: size - 1,
+        }
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+}
+
+/// One of the receiving sides of the [broadcast_ring].
+#[derive(Debug)]
+pub struct Receiver<T> {
+    ring: Arc<Ring<T>>,
+    id: usize,
+    read: usize,
+}
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    fn new(ring: Arc<Ring<T>>, id: usize) -> Self {
+        Receiver { ring, id, read: 0 }
+    }
+
+    /// Retrieve a copy of the next message.
+    /// Returns `Ok(None)` if there is no new message and a [LaggedError] if messages were overwritten.
+    pub fn try_recv(&mut self) -> Result<Option<T>, LaggedError>
+    where
+        T: Clone,
+    {
+        let published = self.ring.published.load(Ordering::Acquire);
+        if self.read == published {
+            return Ok(None);
+        }
+        if published - self.read > self.ring.capacity() {
+            return Err(self.skip(published));
+        }
+
+        let slot = unsafe { self.ring.mem.get_unchecked(self.read & self.ring.mask) };
+        let val = match self.ring.overflow {
+            Overflow::Reject => Self::read_slot(slot),
+            Overflow::Overwrite => {
+                // Announce the read, so the sender does not overwrite the slot meanwhile.
+                let state = slot.state.fetch_add(1, Ordering::Acquire);
+                let val = if state & WRITING == 0 && slot.seq.load(Ordering::Acquire) == self.read {
+                    Some(Self::read_slot(slot))
+                } else {
+                    None
+                };
+                slot.state.fetch_sub(1, Ordering::Release);
+                match val {
+                    Some(val) => val,
+                    None => {
+                        let published = self.ring.published.load(Ordering::Acquire);
+                        return Err(self.skip(published));
+                    }
+                }
+            }
+        };
+        self.read += 1;
+        if self.ring.overflow == Overflow::Reject {
+            self.ring.cursors[self.id].store(self.read, Ordering::Release);
+        }
+        Ok(val)
+    }
+
+    #[inline]
+    fn read_slot(slot: &Slot<T>) -> Option<T>
+    where
+        T: Clone,
+    {
+        #[cfg(loom)]
+        let val = unsafe { slot.value.get().deref() }.clone();
+        #[cfg(not(loom))]
+        let val = unsafe { &*slot.value.get() }.clone();
+        val
+    }
+
+    /// Continues with the oldest message which is certainly not overwritten yet.
+    #[inline]
+    fn skip(&mut self, published: usize) -> LaggedError {
+        // The sender might already overwrite the oldest slot.
+        let oldest = (published + 1).saturating_sub(self.ring.capacity());
+        let oldest = oldest.max(self.read + 1);
+        let lagged = oldest - self.read;
+        self.read = oldest;
+        LaggedError(lagged)
+    }
+
+    /// Returns the total number of messages that the ring can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // A dropped receiver must not stop the sender.
+        self.ring.cursors[self.id].store(usize::MAX, Ordering::Release);
+    }
+}
+
+/// The sending side of the [broadcast_ring].
+#[derive(Debug)]
+pub struct Sender<T> {
+    ring: Arc<Ring<T>>,
+    write: usize,
+    // Cached position of the slowest receiver.
+    slowest: usize,
+}
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+
+impl<T> Sender<T> {
+    fn new(ring: Arc<Ring<T>>) -> Self {
+        Sender {
+            ring,
+            write: 0,
+            slowest: 0,
+        }
+    }
+
+    /// Attempts to send a message to all receivers without blocking.
+    /// Returns a [NoSpaceLeftError] if the ring is full.
+    pub fn try_send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        let ring = &*self.ring;
+        let slot = unsafe { ring.mem.get_unchecked(self.write & ring.mask) };
+        match ring.overflow {
+            Overflow::Reject => {
+                if self.write - self.slowest >= ring.capacity() {
+                    self.slowest = ring
+                        .cursors
+                        .iter()
+                        .map(|cursor| cursor.load(Ordering::Acquire))
+                        .min()
+                        .unwrap_or(self.write)
+                        .min(self.write);
+                    if self.write - self.slowest >= ring.capacity() {
+                        return Err(NoSpaceLeftError(data));
+                    }
+                }
+                Self::write_slot(slot, data, self.write);
+            }
+            Overflow::Overwrite => {
+                if slot
+                    .state
+                    .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_err()
+                {
+                    // A receiver reads the oldest message right now.
+                    return Err(NoSpaceLeftError(data));
+                }
+                Self::write_slot(slot, data, self.write);
+                // Keep the count of receivers which tried to read meanwhile.
+                slot.state.fetch_sub(WRITING, Ordering::Release);
+            }
+        }
+        self.write += 1;
+        ring.published.store(self.write, Ordering::Release);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_slot(slot: &Slot<T>, data: T, seq: usize) {
+        #[cfg(loom)]
+        unsafe {
+            slot.value.get_mut().with(|ptr| {
+                let _ = ptr.replace(Some(data));
+            });
+        }
+        #[cfg(not(loom))]
+        // Drop old value and write new one
+        let _ = unsafe { slot.value.get().replace(Some(data)) };
+        slot.seq.store(seq, Ordering::Release);
+    }
+
+    /// Returns the total number of messages that the ring can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = broadcast_ring(4, 2, Overflow::Reject);
+        w.try_send(vec![0; 15]).unwrap();
+        w.try_send(vec![0; 16]).unwrap();
+
+        for r in r.iter_mut() {
+            assert_eq!(r.try_recv(), Ok(Some(vec![0; 15])));
+            assert_eq!(r.try_recv(), Ok(Some(vec![0; 16])));
+            assert_eq!(r.try_recv(), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_reject_slowest() {
+        let (mut w, mut r) = broadcast_ring::<i32>(4, 2, Overflow::Reject);
+        for i in 0..4 {
+            assert_eq!(w.try_send(i), Ok(()));
+        }
+        assert_eq!(w.try_send(4), Err(NoSpaceLeftError(4)));
+        for i in 0..4 {
+            assert_eq!(r[0].try_recv(), Ok(Some(i)));
+        }
+        // The second receiver still holds back the sender.
+        assert_eq!(w.try_send(4), Err(NoSpaceLeftError(4)));
+        assert_eq!(r[1].try_recv(), Ok(Some(0)));
+        assert_eq!(w.try_send(4), Ok(()));
+        assert_eq!(w.try_send(5), Err(NoSpaceLeftError(5)));
+        // A dropped receiver does not hold back the sender.
+        drop(r.pop());
+        assert_eq!(w.try_send(5), Ok(()));
+        assert_eq!(r[0].try_recv(), Ok(Some(4)));
+        assert_eq!(r[0].try_recv(), Ok(Some(5)));
+    }
+
+    #[test]
+    fn test_overwrite_lagged() {
+        let (mut w, mut r) = broadcast_ring::<i32>(4, 2, Overflow::Overwrite);
+        for i in 0..10 {
+            assert_eq!(w.try_send(i), Ok(()));
+        }
+        // Messages 0..=6 are skipped, because message 6 might be overwritten next.
+        assert_eq!(r[0].try_recv(), Err(LaggedError(7)));
+        assert_eq!(r[0].try_recv(), Ok(Some(7)));
+        assert_eq!(r[0].try_recv(), Ok(Some(8)));
+        assert_eq!(r[0].try_recv(), Ok(Some(9)));
+        assert_eq!(r[0].try_recv(), Ok(None));
+        assert_eq!(r[1].try_recv(), Err(LaggedError(7)));
+        assert_eq!(r[1].try_recv(), Ok(Some(7)));
+    }
+
+    #[test]
+    fn test_overwrite_threaded() {
+        let (mut w, r) = broadcast_ring(8, 3, Overflow::Overwrite);
+        let handles: Vec<_> = r
+            .into_iter()
+            .map(|mut r| {
+                thread::spawn(move || {
+                    let mut last = None;
+                    for _ in 0..10_000 {
+                        if let Ok(Some(val)) = r.try_recv() {
+                            let val: [usize; 20] = val;
+                            assert!(val.iter().all(|entry| *entry == val[0]));
+                            // Messages arrive in order.
+                            assert!(last < Some(val[0]));
+                            last = Some(val[0]);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for i in 0..10_000 {
+            let _ = w.try_send([i; 20]);
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+    }
+}
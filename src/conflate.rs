@@ -0,0 +1,177 @@
+//! A wait-free conflating queue, which only keeps the latest value per key.
+//!
+//! The [Sender] sends updates for a bounded set of keys `0..keys`. If an update for the same key
+//! is still pending, it is replaced instead of taking a new place in the queue.
+//! So the [Receiver] gets at most one update per key, in the order in which the keys became dirty.
+//!
+//! Every key has a [triple_buffer] for its latest value,
+//! and the dirty keys are passed through a [spsc] queue, which can never be full.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::conflate;
+//!
+//! //                            Number of keys ─╮
+//! let (mut tx, mut rx) = conflate::conflate::<f64>(6);
+//! tx.send(2, 0.1);
+//! tx.send(0, 0.5);
+//! tx.send(2, 0.2);
+//! assert_eq!(rx.try_recv(), Some((2, 0.2)));
+//! assert_eq!(rx.try_recv(), Some((0, 0.5)));
+//! assert_eq!(rx.try_recv(), None);
+//! ```
+
+use crate::import::{Arc, AtomicBool, Ordering};
+use crate::spsc;
+use crate::triple_buffer::{self, Reader, Writer};
+
+/// Create a new wait-free conflating queue for the keys `0..keys`.
+/// # Example
+/// ```rust
+/// use waitfree_sync::conflate;
+///
+/// //                    Value type ──╮      ╭─ Number of keys
+/// let (tx, rx) = conflate::conflate::<u64>(16);
+/// ```
+pub fn conflate<V>(keys: usize) -> (Sender<V>, Receiver<V>) {
+    // Every key is queued at most once.
+    let (queue_tx, queue_rx) = spsc::spsc(keys.next_power_of_two().max(2));
+    let dirty: Arc<Box<[AtomicBool]>> =
+        Arc::new((0..keys).map(|_| AtomicBool::new(false)).collect());
+    let (writers, readers): (Vec<_>, Vec<_>) =
+        (0..keys).map(|_| triple_buffer::triple_buffer()).unzip();
+
+    let s = Sender {
+        values: writers.into_boxed_slice(),
+        dirty: dirty.clone(),
+        queue: queue_tx,
+    };
+    let r = Receiver {
+        values: readers.into_boxed_slice(),
+        dirty,
+        queue: queue_rx,
+    };
+    (s, r)
+}
+
+/// The sending side of the [conflate] queue.
+#[derive(Debug)]
+pub struct Sender<V> {
+    values: Box<[Writer<V>]>,
+    dirty: Arc<Box<[AtomicBool]>>,
+    queue: spsc::Sender<usize>,
+}
+
+impl<V> Sender<V> {
+    /// Sends the latest value of a key. A pending value of the same key is replaced and dropped.
+    /// # Panic
+    /// Panics if the key is not below the number of keys.
+    pub fn send(&mut self, key: usize, value: V) {
+        self.values[key].write(value);
+        if !self.dirty[key].swap(true, Ordering::AcqRel) {
+            // There is space, since the key is not queued.
+            let _ = self.queue.try_send(key);
+        }
+    }
+
+    /// Returns the number of keys.
+    #[inline]
+    pub fn keys(&self) -> usize {
+        self.values.len()
+    }
+}
+
+/// The receiving side of the [conflate] queue.
+#[derive(Debug)]
+pub struct Receiver<V> {
+    values: Box<[Reader<V>]>,
+    dirty: Arc<Box<[AtomicBool]>>,
+    queue: spsc::Receiver<usize>,
+}
+
+impl<V> Receiver<V> {
+    /// Retrieve the latest value of the key which became dirty first, together with its key.
+    /// Returns [None] if no key is dirty.
+    pub fn try_recv(&mut self) -> Option<(usize, V)> {
+        // A key can be queued again while its value is taken, so its next entry may have no value.
+        // Such entries are skipped, but at most once per key to stay wait-free.
+        for _ in 0..self.keys() {
+            let key = self.queue.try_recv()?;
+            // Clear the flag before taking the value, so a later value queues the key again.
+            self.dirty[key].swap(false, Ordering::AcqRel);
+            if let Some(value) = self.values[key].take() {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    /// Returns the number of keys.
+    #[inline]
+    pub fn keys(&self) -> usize {
+        self.values.len()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut tx, mut rx) = conflate(3);
+        assert_eq!(tx.keys(), 3);
+        assert_eq!(rx.try_recv(), None);
+        tx.send(1, "a");
+        tx.send(0, "b");
+        tx.send(1, "c");
+        tx.send(2, "d");
+        tx.send(0, "e");
+        assert_eq!(rx.try_recv(), Some((1, "c")));
+        tx.send(1, "f");
+        assert_eq!(rx.try_recv(), Some((0, "e")));
+        assert_eq!(rx.try_recv(), Some((2, "d")));
+        assert_eq!(rx.try_recv(), Some((1, "f")));
+        assert_eq!(rx.try_recv(), None);
+    }
+
+    #[test]
+    fn test_never_full() {
+        let (mut tx, mut rx) = conflate(5);
+        for i in 0..100 {
+            tx.send(i % 5, i);
+        }
+        let received: Vec<_> = core::iter::from_fn(|| rx.try_recv()).collect();
+        assert_eq!(received, vec![(0, 95), (1, 96), (2, 97), (3, 98), (4, 99)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_key() {
+        let (mut tx, _) = conflate(2);
+        tx.send(2, 0u8);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut tx, mut rx) = conflate(4);
+        let sender_thread = thread::spawn(move || {
+            for i in 0..1000 {
+                tx.send(i % 4, i);
+            }
+        });
+        let mut last = [None; 4];
+        while last.iter().any(|last| *last < Some(996)) {
+            if let Some((key, value)) = rx.try_recv() {
+                assert_eq!(value % 4, key);
+                // The values of a key are never received twice or out of order.
+                assert!(Some(value) > last[key]);
+                last[key] = Some(value);
+            }
+        }
+        assert!(sender_thread.join().is_ok());
+        assert_eq!(rx.try_recv(), None);
+    }
+}
@@ -0,0 +1,270 @@
+//! A wait-free request/response channel between two threads, e.g. a real-time and a non-real-time thread.
+//!
+//! It consists of two [spsc] queues, one for the requests and one for the responses.
+//! Every request gets a [RequestId], which is returned with its response.
+//! The number of requests in flight is bounded by the capacity, so the responses always fit into their queue.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::duplex;
+//!
+//! //                      Request ──╮    ╭── Response  ╭─ Capacity
+//! let (mut client, mut server) = duplex::duplex::<u32, u64>(8);
+//! let id = client.try_request(21).unwrap();
+//!
+//! let (req_id, req) = server.try_recv().unwrap();
+//! server.try_respond(req_id, req as u64 * 2).unwrap();
+//!
+//! assert_eq!(client.try_recv(), Some((id, 42)));
+//! ```
+
+use crate::spsc::{self, NoSpaceLeftError};
+use std::error::Error;
+use std::fmt::Debug;
+
+/// Create a new wait-free duplex channel. The `capacity` must be a power of two, which is validate during runtime.
+/// It is the maximum number of requests in flight.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::duplex;
+///
+/// //                Request ──╮    ╭── Response  ╭─ Capacity
+/// let (client, server) = duplex::duplex::<u32, u64>(8);
+/// ```
+pub fn duplex<Req, Resp>(capacity: usize) -> (Requester<Req, Resp>, Responder<Req, Resp>) {
+    let (req_tx, req_rx) = spsc::spsc(capacity);
+    let (resp_tx, resp_rx) = spsc::spsc(capacity);
+
+    let requester = Requester {
+        tx: req_tx,
+        rx: resp_rx,
+        next_id: 0,
+        in_flight: 0,
+    };
+    let responder = Responder {
+        tx: resp_tx,
+        rx: req_rx,
+        outstanding: 0,
+    };
+    (requester, responder)
+}
+
+/// Correlates a response with its request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// Returns the raw value of the id.
+    #[inline]
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Indicates that a response was sent although no request is waiting for one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoRequestError<T>(T);
+impl<T> NoRequestError<T> {
+    /// Returns the response which could not be sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T: Debug> Error for NoRequestError<T> {}
+impl<T> core::fmt::Display for NoRequestError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No request is waiting for a response.")
+    }
+}
+
+/// The requesting side of the [duplex] channel.
+#[derive(Debug)]
+pub struct Requester<Req, Resp> {
+    tx: spsc::Sender<(RequestId, Req)>,
+    rx: spsc::Receiver<(RequestId, Resp)>,
+    next_id: u64,
+    in_flight: usize,
+}
+
+impl<Req, Resp> Requester<Req, Resp> {
+    /// Attempts to send a request without blocking and returns its [RequestId].
+    /// Returns a [NoSpaceLeftError] if the maximum number of requests is in flight.
+    pub fn try_request(&mut self, request: Req) -> Result<RequestId, NoSpaceLeftError<Req>> {
+        if self.in_flight == self.capacity() {
+            return Err(NoSpaceLeftError(request));
+        }
+        let id = RequestId(self.next_id);
+        self.tx
+            .try_send((id, request))
+            .map_err(|NoSpaceLeftError((_, request))| NoSpaceLeftError(request))?;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.in_flight += 1;
+        Ok(id)
+    }
+
+    /// Retrieve the next response together with the [RequestId] of its request.
+    /// Returns [None] if there is no response.
+    pub fn try_recv(&mut self) -> Option<(RequestId, Resp)> {
+        let response = self.rx.try_recv()?;
+        // The responder never sends more responses than requests.
+        self.in_flight -= 1;
+        Some(response)
+    }
+
+    /// Returns the number of requests which have not been answered yet.
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Returns the maximum number of requests in flight.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.tx.capacity()
+    }
+}
+
+/// The responding side of the [duplex] channel.
+#[derive(Debug)]
+pub struct Responder<Req, Resp> {
+    tx: spsc::Sender<(RequestId, Resp)>,
+    rx: spsc::Receiver<(RequestId, Req)>,
+    // Received requests which have not been answered yet.
+    outstanding: usize,
+}
+
+impl<Req, Resp> Responder<Req, Resp> {
+    /// Retrieve the next request together with its [RequestId].
+    /// Returns [None] if there is no request.
+    pub fn try_recv(&mut self) -> Option<(RequestId, Req)> {
+        let request = self.rx.try_recv()?;
+        self.outstanding += 1;
+        Some(request)
+    }
+
+    /// Attempts to send the response to the request with the given [RequestId] without blocking.
+    /// Returns a [NoRequestError] if every received request has already been answered.
+    /// Only the number of responses is checked, not the [RequestId].
+    pub fn try_respond(
+        &mut self,
+        id: RequestId,
+        response: Resp,
+    ) -> Result<(), NoRequestError<Resp>> {
+        if self.outstanding == 0 {
+            return Err(NoRequestError(response));
+        }
+        // There is space, since the requester limits the number of requests in flight.
+        self.tx
+            .try_send((id, response))
+            .map_err(|NoSpaceLeftError((_, response))| NoRequestError(response))?;
+        self.outstanding -= 1;
+        Ok(())
+    }
+
+    /// Returns the number of received requests which have not been answered yet.
+    #[inline]
+    pub fn outstanding(&self) -> usize {
+        self.outstanding
+    }
+
+    /// Returns the maximum number of requests in flight.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.tx.capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut c, mut s) = duplex::<u32, String>(4);
+        let id1 = c.try_request(1).unwrap();
+        let id2 = c.try_request(2).unwrap();
+        assert_ne!(id1, id2);
+        assert_eq!(c.in_flight(), 2);
+
+        // Responses can be sent in any order.
+        let (rid1, req1) = s.try_recv().unwrap();
+        let (rid2, req2) = s.try_recv().unwrap();
+        assert_eq!((rid1, req1), (id1, 1));
+        assert_eq!((rid2, req2), (id2, 2));
+        s.try_respond(rid2, "two".to_string()).unwrap();
+        s.try_respond(rid1, "one".to_string()).unwrap();
+
+        assert_eq!(c.try_recv(), Some((id2, "two".to_string())));
+        assert_eq!(c.try_recv(), Some((id1, "one".to_string())));
+        assert_eq!(c.try_recv(), None);
+        assert_eq!(c.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_in_flight_limit() {
+        let (mut c, mut s) = duplex::<u32, u32>(2);
+        c.try_request(1).unwrap();
+        c.try_request(2).unwrap();
+        assert_eq!(c.try_request(3), Err(NoSpaceLeftError(3)));
+
+        // Receiving the request does not free the slot, only the response does.
+        let (id, req) = s.try_recv().unwrap();
+        assert_eq!(c.try_request(3), Err(NoSpaceLeftError(3)));
+        s.try_respond(id, req).unwrap();
+        assert_eq!(c.try_request(3), Err(NoSpaceLeftError(3)));
+        assert_eq!(c.try_recv(), Some((id, 1)));
+        assert!(c.try_request(3).is_ok());
+    }
+
+    #[test]
+    fn test_unsolicited_response() {
+        let (mut c, mut s) = duplex::<u32, u32>(2);
+        assert_eq!(s.try_respond(RequestId(0), 5), Err(NoRequestError(5)));
+        let id = c.try_request(1).unwrap();
+        let (rid, req) = s.try_recv().unwrap();
+        assert_eq!(s.outstanding(), 1);
+        s.try_respond(rid, req).unwrap();
+        // A second response to the same request is rejected.
+        assert_eq!(s.try_respond(rid, req), Err(NoRequestError(1)));
+        assert_eq!(s.outstanding(), 0);
+        assert_eq!(c.try_recv(), Some((id, 1)));
+        assert_eq!(c.try_recv(), None);
+        assert_eq!(c.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut c, mut s) = duplex::<u64, u64>(8);
+        let server_thread = thread::spawn(move || {
+            let mut answered = 0;
+            while answered < 1000 {
+                if let Some((id, req)) = s.try_recv() {
+                    s.try_respond(id, req * 2).unwrap();
+                    answered += 1;
+                }
+            }
+        });
+
+        let mut pending = std::collections::HashMap::new();
+        let mut sent = 0;
+        let mut received = 0;
+        while received < 1000 {
+            if sent < 1000 {
+                if let Ok(id) = c.try_request(sent) {
+                    pending.insert(id, sent);
+                    sent += 1;
+                }
+            }
+            if let Some((id, resp)) = c.try_recv() {
+                assert_eq!(pending.remove(&id).unwrap() * 2, resp);
+                received += 1;
+            }
+        }
+        assert!(server_thread.join().is_ok());
+    }
+}
@@ -0,0 +1,154 @@
+//! Wait-free deferred dropping of values to keep deallocation out of real-time threads.
+//!
+//! Dropping a value can free memory, which can take an unbounded amount of time.
+//! Instead of dropping a value, the real-time thread passes it to the [Disposer],
+//! and a non-real-time thread drops it later with the [Collector].
+//! It is built on a [spsc] queue.
+//!
+//! Values displaced by [triple_buffer::Writer::replace](crate::triple_buffer::Writer::replace)
+//! or old messages from [spsc::Receiver::try_recv] can be disposed this way.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::{garbage, spsc};
+//!
+//! let (mut tx, mut rx) = spsc::spsc(8);
+//! let (mut disposer, mut collector) = garbage::garbage(8);
+//!
+//! tx.try_send(vec![0u8; 1024]).unwrap();
+//! // In the real-time thread
+//! let msg = rx.try_recv().unwrap();
+//! disposer.dispose(msg).unwrap();
+//!
+//! // In the non-real-time thread
+//! assert_eq!(collector.collect(), 1);
+//! ```
+
+use crate::spsc::{self, NoSpaceLeftError};
+
+/// Create a new wait-free garbage queue. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::garbage;
+///
+/// //                       Data type ──╮        ╭─ Capacity
+/// let (disposer, collector) = garbage::garbage::<Vec<u8>>(8);
+/// ```
+pub fn garbage<T>(capacity: usize) -> (Disposer<T>, Collector<T>) {
+    let (tx, rx) = spsc::spsc(capacity);
+    (Disposer { tx }, Collector { rx })
+}
+
+/// The side of the [garbage] queue for the real-time thread, which disposes values instead of dropping them.
+#[derive(Debug)]
+pub struct Disposer<T> {
+    tx: spsc::Sender<T>,
+}
+
+impl<T> Disposer<T> {
+    /// Passes the value to the [Collector] to drop it there.
+    /// Returns a [NoSpaceLeftError] with the value if the queue is full.
+    /// The value can be taken back with [NoSpaceLeftError::into_inner] to retry later.
+    #[inline]
+    pub fn dispose(&mut self, value: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.tx.try_send(value)
+    }
+
+    /// Passes the value to the [Collector] if there is one.
+    /// This fits values returned as `Option`, e.g. by [triple_buffer::Writer::replace](crate::triple_buffer::Writer::replace).
+    #[inline]
+    pub fn dispose_opt(&mut self, value: Option<T>) -> Result<(), NoSpaceLeftError<T>> {
+        match value {
+            Some(value) => self.dispose(value),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the total number of values that can be pending at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.tx.capacity()
+    }
+}
+
+/// The side of the [garbage] queue for the non-real-time thread, which drops the disposed values.
+#[derive(Debug)]
+pub struct Collector<T> {
+    rx: spsc::Receiver<T>,
+}
+
+impl<T> Collector<T> {
+    /// Drops all pending values and returns their number.
+    pub fn collect(&mut self) -> usize {
+        let mut count = 0;
+        while let Some(value) = self.rx.try_recv() {
+            drop(value);
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the total number of values that can be pending at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.rx.capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::triple_buffer;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut d, mut c) = garbage(2);
+        let value = Arc::new(1);
+        d.dispose(value.clone()).unwrap();
+        d.dispose_opt(None).unwrap();
+        d.dispose_opt(Some(value.clone())).unwrap();
+        assert_eq!(d.dispose(value.clone()).unwrap_err().0, value);
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        assert_eq!(c.collect(), 2);
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert_eq!(c.collect(), 0);
+    }
+
+    #[test]
+    fn test_triple_buffer() {
+        let (mut w, mut r) = triple_buffer::triple_buffer();
+        let (mut d, mut c) = garbage(4);
+        let value = Arc::new(1);
+        for _ in 0..4 {
+            d.dispose_opt(w.replace(value.clone())).unwrap();
+        }
+        assert_eq!(r.try_read(), Some(value.clone()));
+        // Only the displaced values are collected.
+        assert_eq!(c.collect(), 2);
+        assert_eq!(Arc::strong_count(&value), 3);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut d, mut c) = garbage(8);
+        let collector_thread = thread::spawn(move || {
+            let mut collected = 0;
+            while collected < 1000 {
+                collected += c.collect();
+            }
+        });
+        let mut disposed = 0;
+        while disposed < 1000 {
+            if d.dispose(vec![disposed; 16]).is_ok() {
+                disposed += 1;
+            }
+        }
+        assert!(collector_thread.join().is_ok());
+    }
+}
@@ -0,0 +1,250 @@
+//! A wait-free histogram to collect statistics like the cycle-time jitter of a real-time thread.
+//!
+//! Values are recorded into fixed log-linear buckets: Every power of two is split into
+//! [SUB_BUCKETS] linear buckets, so the relative error of a bucket is at most `1 / SUB_BUCKETS`.
+//! Recording a value consists of two atomic increments, of its bucket and of the sum, so it is wait-free
+//! even with concurrent recorders and never allocates. The minimum and maximum are derived from the buckets.
+//! A [Snapshot] can be taken concurrently from any thread.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::histogram::Histogram;
+//!
+//! let histogram = Histogram::new();
+//! for cycle_time in [980, 1000, 1010, 1500] {
+//!     histogram.record(cycle_time);
+//! }
+//!
+//! let snapshot = histogram.snapshot();
+//! assert_eq!(snapshot.count(), 4);
+//! // The maximum is the end of the highest non-empty bucket.
+//! assert_eq!(snapshot.max(), Some(1535));
+//! assert_eq!(snapshot.quantile(0.5), Some(1023));
+//! ```
+
+use crate::import::{AtomicU64, Ordering};
+use core::ops::RangeInclusive;
+
+const SUB_BITS: u32 = 3;
+
+/// The number of linear buckets per power of two.
+pub const SUB_BUCKETS: usize = 1 << SUB_BITS;
+
+/// The total number of buckets, which covers all values of `u64`.
+pub const BUCKETS: usize = (u64::BITS - SUB_BITS + 1) as usize * SUB_BUCKETS;
+
+#[inline]
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let msb = u64::BITS - 1 - value.leading_zeros();
+    let shift = msb - SUB_BITS;
+    let sub = (value >> shift) as usize & (SUB_BUCKETS - 1);
+    (shift as usize + 1) * SUB_BUCKETS + sub
+}
+
+#[inline]
+fn bucket_range(idx: usize) -> RangeInclusive<u64> {
+    if idx < SUB_BUCKETS {
+        return idx as u64..=idx as u64;
+    }
+    let shift = idx / SUB_BUCKETS - 1;
+    let low = ((SUB_BUCKETS + idx % SUB_BUCKETS) as u64) << shift;
+    low..=low + ((1 << shift) - 1)
+}
+
+/// A wait-free histogram of `u64` values with log-linear buckets.
+///
+/// Any number of threads can record values concurrently, e.g. if it is shared with an `Arc`.
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: Box<[AtomicU64]>,
+    sum: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Histogram {
+    /// Create a new empty histogram. All buckets are allocated up front.
+    pub fn new() -> Self {
+        Histogram {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a value.
+    #[inline]
+    pub fn record(&self, value: u64) {
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.buckets[bucket_index(value)].fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns a snapshot of the histogram.
+    ///
+    /// The buckets are read one after another, so values recorded in the meantime may be
+    /// counted in some buckets but not in others.
+    pub fn snapshot(&self) -> Snapshot {
+        let counts = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Acquire))
+            .collect();
+        Snapshot {
+            counts,
+            sum: self.sum.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A snapshot of a [Histogram].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    counts: Box<[u64]>,
+    sum: u64,
+}
+
+impl Snapshot {
+    /// Returns the total number of values.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the sum of all values. It wraps around on overflow.
+    #[inline]
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Returns a lower bound of the smallest value, which is the start of the lowest non-empty bucket.
+    /// Returns [None] if there are no values.
+    pub fn min(&self) -> Option<u64> {
+        let idx = self.counts.iter().position(|count| *count > 0)?;
+        Some(*bucket_range(idx).start())
+    }
+
+    /// Returns an upper bound of the largest value, which is the end of the highest non-empty bucket.
+    /// Returns [None] if there are no values.
+    pub fn max(&self) -> Option<u64> {
+        let idx = self.counts.iter().rposition(|count| *count > 0)?;
+        Some(*bucket_range(idx).end())
+    }
+
+    /// Returns the mean of all values or [None] if there are no values.
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.sum as f64 / count as f64)
+    }
+
+    /// Returns an upper bound of the value which is not exceeded by the fraction `quantile` of the values,
+    /// e.g. `0.99` for the 99th percentile. The bound is the end of the bucket.
+    /// Returns [None] if there are no values.
+    pub fn quantile(&self, quantile: f64) -> Option<u64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((count as f64 * quantile).ceil() as u64).clamp(1, count);
+        let mut seen = 0;
+        for (idx, bucket) in self.counts.iter().enumerate() {
+            seen += bucket;
+            if seen >= rank {
+                return Some(*bucket_range(idx).end());
+            }
+        }
+        self.max()
+    }
+
+    /// Returns an iterator over the non-empty buckets with their range of values and their count.
+    pub fn iter(&self) -> impl Iterator<Item = (RangeInclusive<u64>, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(idx, count)| (bucket_range(idx), *count))
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let histogram = Histogram::new();
+        assert_eq!(histogram.snapshot().count(), 0);
+        assert_eq!(histogram.snapshot().min(), None);
+        assert_eq!(histogram.snapshot().quantile(0.5), None);
+
+        for value in [3, 8, 9, 100, 100] {
+            histogram.record(value);
+        }
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count(), 5);
+        assert_eq!(snapshot.sum(), 220);
+        assert_eq!(snapshot.mean(), Some(44.0));
+        assert_eq!(snapshot.min(), Some(3));
+        // The minimum and maximum are the bounds of their buckets.
+        assert_eq!(snapshot.max(), Some(103));
+        assert_eq!(snapshot.quantile(0.0), Some(3));
+        assert_eq!(snapshot.quantile(0.5), Some(9));
+        assert_eq!(snapshot.quantile(1.0), Some(103));
+        assert_eq!(
+            snapshot.iter().collect::<Vec<_>>(),
+            vec![(3..=3, 1), (8..=8, 1), (9..=9, 1), (96..=103, 2)]
+        );
+    }
+
+    #[test]
+    fn test_buckets() {
+        // The buckets cover all values without gaps.
+        let mut next = 0;
+        for idx in 0..BUCKETS {
+            let range = bucket_range(idx);
+            assert_eq!(*range.start(), next);
+            assert_eq!(bucket_index(*range.start()), idx);
+            assert_eq!(bucket_index(*range.end()), idx);
+            // The relative error is bounded.
+            assert!((range.end() - range.start()) <= range.start() / SUB_BUCKETS as u64);
+            next = range.end().wrapping_add(1);
+        }
+        assert_eq!(next, 0);
+        assert_eq!(bucket_index(u64::MAX), BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let histogram = Arc::new(Histogram::new());
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let histogram = histogram.clone();
+                thread::spawn(move || {
+                    for value in 0..1000 {
+                        histogram.record(value);
+                    }
+                })
+            })
+            .collect();
+        let mut last = 0;
+        while last < 3000 {
+            let count = histogram.snapshot().count();
+            assert!(count >= last);
+            last = count;
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.sum(), 3 * 999 * 1000 / 2);
+        assert_eq!(snapshot.min(), Some(0));
+        assert_eq!(snapshot.max(), Some(1023));
+    }
+}
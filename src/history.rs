@@ -0,0 +1,370 @@
+//! Wait-free single-producer single-consumer history buffer, where the reader sees the last `K` published values.
+//!
+//! It generalizes the [triple_buffer](crate::triple_buffer): Instead of the index of a single slot,
+//! the shared word contains the indices of the last `K` written slots, newest first.
+//! With `2 * K + 1` slots, the writer always finds a slot which is neither held by the reader nor published,
+//! so neither side ever waits. The reader gets consecutive writes, e.g. for interpolation or derivative estimation.
+//! If the writer writes more than `K` values between two updates of the reader, the older ones are skipped,
+//! which the reader can detect with the [sequence number](Reader::sequence).
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::history;
+//!
+//! let (mut wr, mut rd) = history::history(3);
+//! for i in 1..=4 {
+//!     wr.write(i);
+//! }
+//! rd.update();
+//! assert_eq!(rd.iter().copied().collect::<Vec<_>>(), vec![4, 3, 2]);
+//! assert_eq!(rd.sequence(), Some(3));
+//! ```
+
+use crate::import::{Arc, AtomicU64, Ordering, UnsafeCell};
+use crossbeam_utils::CachePadded;
+
+/// The maximum length of the history.
+pub const MAX_HISTORY: usize = 11;
+
+const NEW_DATA_FLAG: u64 = 1 << 63;
+const INDEX_BITS: usize = 5;
+const INDEX_MASK: u64 = (1 << INDEX_BITS) - 1;
+const COUNT_SHIFT: usize = MAX_HISTORY * INDEX_BITS;
+const COUNT_MASK: u64 = 0b1111;
+
+/// An ordered list of up to [MAX_HISTORY] slot indices, packed into one word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Indices(u64);
+
+impl Indices {
+    const EMPTY: Indices = Indices(0);
+
+    #[inline]
+    fn len(self) -> usize {
+        ((self.0 >> COUNT_SHIFT) & COUNT_MASK) as usize
+    }
+
+    #[inline]
+    fn get(self, i: usize) -> usize {
+        ((self.0 >> (i * INDEX_BITS)) & INDEX_MASK) as usize
+    }
+
+    /// Returns the indices with `idx` as newest entry, dropping the oldest entry beyond `max`.
+    #[inline]
+    fn push_front(self, idx: usize, max: usize) -> Indices {
+        let len = (self.len() + 1).min(max);
+        let entries = (self.0 << INDEX_BITS | idx as u64) & ((1 << (len * INDEX_BITS)) - 1);
+        Indices(entries | (len as u64) << COUNT_SHIFT)
+    }
+
+    /// Returns a bit mask of the contained slots.
+    #[inline]
+    fn slots(self) -> u32 {
+        (0..self.len()).fold(0, |mask, i| mask | 1 << self.get(i))
+    }
+}
+
+// Every value is stored with its sequence number.
+type Slot<T> = UnsafeCell<Option<(u64, T)>>;
+
+#[derive(Debug)]
+struct Shared<T> {
+    mem: Box<[Slot<T>]>,
+    latest: CachePadded<AtomicU64>,
+}
+
+/// Create a new wait-free history buffer, whose reader sees the last `k` published values.
+/// # Panic
+/// Panics if `k` is zero or larger than [MAX_HISTORY].
+/// # Example
+/// ```rust
+/// use waitfree_sync::history;
+///
+/// //                Data type ──╮       ╭─ Length of the history
+/// let (wr, rd) = history::history::<f64>(4);
+/// ```
+pub fn history<T>(k: usize) -> (Writer<T>, Reader<T>) {
+    assert!(
+        (1..=MAX_HISTORY).contains(&k),
+        "The length of the history must be between 1 and {MAX_HISTORY}"
+    );
+    let chan = Arc::new(Shared {
+        mem: (0..2 * k + 1).map(|_| UnsafeCell::new(None)).collect(),
+        latest: CachePadded::new(AtomicU64::new(Indices::EMPTY.0)),
+    });
+
+    let w = Writer {
+        shared: chan.clone(),
+        k,
+        published: Indices::EMPTY,
+        reader: Indices::EMPTY,
+        sequence: 0,
+    };
+    let r = Reader {
+        shared: chan,
+        held: Indices::EMPTY,
+    };
+    (w, r)
+}
+
+/// The reading side of the [history] buffer.
+#[derive(Debug)]
+pub struct Reader<T> {
+    shared: Arc<Shared<T>>,
+    held: Indices,
+}
+unsafe impl<T: Send> Send for Reader<T> {}
+unsafe impl<T: Send> Sync for Reader<T> {}
+
+impl<T> Reader<T> {
+    /// Takes over the latest history if the [Writer] has written something new.
+    /// Returns `true` if the history changed.
+    ///
+    /// The history only changes with this call, so several values can be read consistently.
+    #[inline]
+    pub fn update(&mut self) -> bool {
+        if self.shared.latest.load(Ordering::Acquire) & NEW_DATA_FLAG == 0 {
+            return false;
+        }
+        // Keep the history in the shared word, so the writer sees that it was taken over.
+        let latest = self
+            .shared
+            .latest
+            .fetch_and(!NEW_DATA_FLAG, Ordering::AcqRel);
+        self.held = Indices(latest & !NEW_DATA_FLAG);
+        true
+    }
+
+    /// Returns the number of values in the history, which is at most `k`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Returns `true` if the [Writer] has not written anything before the last [update](Reader::update).
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.held.len() == 0
+    }
+
+    /// Returns the sequence number of the newest value, which counts the writes starting at `0`.
+    /// The value at position `i` has the sequence number `sequence - i`.
+    #[inline]
+    pub fn sequence(&self) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+        let slot = &self.shared.mem[self.held.get(0)];
+        #[cfg(loom)]
+        let seq = unsafe { slot.get().deref() }.as_ref().map(|(seq, _)| *seq);
+        #[cfg(not(loom))]
+        let seq = unsafe { &*slot.get() }.as_ref().map(|(seq, _)| *seq);
+        seq
+    }
+
+    /// Returns a reference to the value at position `i` of the history, where `0` is the newest value.
+    /// Returns [None] if the history is shorter.
+    #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.held.len() {
+            return None;
+        }
+        let val = unsafe { &*self.shared.mem[self.held.get(i)].get() };
+        val.as_ref().map(|(_, val)| val)
+    }
+
+    /// Returns a copy of the value at position `i` of the history, where `0` is the newest value.
+    /// Returns [None] if the history is shorter.
+    #[inline]
+    pub fn get_cloned(&self, i: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        if i >= self.held.len() {
+            return None;
+        }
+        let slot = &self.shared.mem[self.held.get(i)];
+        #[cfg(loom)]
+        let val = unsafe { slot.get().deref() }
+            .as_ref()
+            .map(|(_, val)| val.clone());
+        #[cfg(not(loom))]
+        let val = unsafe { &*slot.get() }.as_ref().map(|(_, val)| val.clone());
+        val
+    }
+
+    /// Returns an iterator over the history, starting with the newest value.
+    #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len()).filter_map(|i| self.get(i))
+    }
+}
+
+/// The writing side of the [history] buffer.
+#[derive(Debug)]
+pub struct Writer<T> {
+    shared: Arc<Shared<T>>,
+    k: usize,
+    // The history in the shared word.
+    published: Indices,
+    // The history which the reader took over last, as far as the writer knows.
+    reader: Indices,
+    sequence: u64,
+}
+unsafe impl<T: Send> Send for Writer<T> {}
+unsafe impl<T: Send> Sync for Writer<T> {}
+
+impl<T> Writer<T> {
+    /// Writes a new value.
+    #[inline]
+    pub fn write(&mut self, data: T) {
+        // Drop old value
+        let _ = self.replace(data);
+    }
+
+    /// Writes a new value and returns the old value of the reused slot instead of dropping it.
+    /// This allows to drop the old value in another thread, e.g. with the [garbage](crate::garbage) module.
+    #[inline]
+    pub fn replace(&mut self, data: T) -> Option<T> {
+        // The reader holds either the published history or the one it took over before.
+        let in_use = self.published.slots() | self.reader.slots();
+        let idx = (!in_use).trailing_zeros() as usize;
+        debug_assert!(idx < self.shared.mem.len());
+
+        let value = Some((self.sequence, data));
+        #[cfg(loom)]
+        let old = unsafe {
+            self.shared.mem[idx]
+                .get_mut()
+                .with(|ptr| ptr.replace(value))
+        };
+        #[cfg(not(loom))]
+        let old = unsafe { self.shared.mem[idx].get().replace(value) };
+        self.sequence += 1;
+
+        let history = self.published.push_front(idx, self.k);
+        let previous = self
+            .shared
+            .latest
+            .swap(history.0 | NEW_DATA_FLAG, Ordering::AcqRel);
+        if previous & NEW_DATA_FLAG == 0 {
+            // The reader took over the previous history.
+            self.reader = self.published;
+        }
+        self.published = history;
+        old.map(|(_, val)| val)
+    }
+
+    /// Returns the length of the history.
+    #[inline]
+    pub fn history_len(&self) -> usize {
+        self.k
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = history(3);
+        assert!(!r.update());
+        assert!(r.is_empty());
+        assert_eq!(r.sequence(), None);
+
+        w.write(vec![1]);
+        assert!(r.update());
+        assert_eq!(r.get(0), Some(&vec![1]));
+        assert_eq!(r.get(1), None);
+        w.write(vec![2]);
+        w.write(vec![3]);
+        // The history only changes with an update.
+        assert_eq!(r.len(), 1);
+        assert!(r.update());
+        assert_eq!(
+            r.iter().collect::<Vec<_>>(),
+            vec![&vec![3], &vec![2], &vec![1]]
+        );
+        w.write(vec![4]);
+        assert!(r.update());
+        assert!(!r.update());
+        assert_eq!(r.get_cloned(0), Some(vec![4]));
+        assert_eq!(r.get_cloned(2), Some(vec![2]));
+        assert_eq!(r.get_cloned(3), None);
+        assert_eq!(r.sequence(), Some(3));
+    }
+
+    #[test]
+    fn test_indices() {
+        let mut indices = Indices::EMPTY;
+        for idx in [3, 22, 0, 7] {
+            indices = indices.push_front(idx, MAX_HISTORY);
+        }
+        assert_eq!(indices.len(), 4);
+        assert_eq!(
+            (0..4).map(|i| indices.get(i)).collect::<Vec<_>>(),
+            vec![7, 0, 22, 3]
+        );
+        assert_eq!(indices.slots(), 1 << 0 | 1 << 3 | 1 << 7 | 1 << 22);
+
+        for idx in 0..MAX_HISTORY + 2 {
+            indices = indices.push_front(idx, MAX_HISTORY);
+        }
+        assert_eq!(indices.len(), MAX_HISTORY);
+        assert_eq!(indices.get(0), MAX_HISTORY + 1);
+        assert_eq!(indices.get(MAX_HISTORY - 1), 2);
+        assert_eq!(indices.0 & NEW_DATA_FLAG, 0);
+    }
+
+    #[test]
+    fn test_slots_reused() {
+        let (mut w, mut r) = history(2);
+        for i in 0..3 {
+            assert_eq!(w.replace(i), None);
+        }
+        // The slot of 0 is neither published nor held by the reader.
+        assert_eq!(w.replace(3), Some(0));
+        r.update();
+        assert_eq!(w.replace(4), Some(1));
+        // The reader still holds the slots of 3 and 2.
+        assert_eq!(w.replace(5), None);
+        assert_eq!(r.iter().copied().collect::<Vec<_>>(), vec![3, 2]);
+        r.update();
+        assert_eq!(r.iter().copied().collect::<Vec<_>>(), vec![5, 4]);
+        assert_eq!(w.history_len(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_long() {
+        let _ = history::<u8>(MAX_HISTORY + 1);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut w, mut r) = history(4);
+        let reader_thread = thread::spawn(move || {
+            let mut last = None;
+            while last < Some(999) {
+                if r.update() {
+                    let seq = r.sequence().unwrap();
+                    assert!(Some(seq) > last);
+                    // The history consists of consecutive writes.
+                    for (i, val) in r.iter().enumerate() {
+                        assert_eq!(*val, [seq - i as u64; 16]);
+                    }
+                    last = Some(seq);
+                }
+            }
+        });
+        for i in 0..1000 {
+            w.write([i; 16]);
+        }
+        assert!(reader_thread.join().is_ok());
+    }
+}
@@ -0,0 +1,162 @@
+//! A wait-free [spsc] queue, which measures how long every message stays in the queue.
+//!
+//! The [Sender] stores the enqueue time of the monotonic clock together with the message.
+//! The [Receiver] returns the residency latency with every message and records it in nanoseconds
+//! into a [Histogram], which can be read from any thread without locks.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::latency;
+//!
+//! let (mut tx, mut rx) = latency::latency(8);
+//! let histogram = rx.histogram();
+//!
+//! tx.try_send(42).unwrap();
+//! let (msg, latency) = rx.try_recv().unwrap();
+//! assert_eq!(msg, 42);
+//!
+//! let snapshot = histogram.snapshot();
+//! assert_eq!(snapshot.count(), 1);
+//! assert!(snapshot.max() >= Some(latency.as_nanos() as u64));
+//! ```
+
+use crate::histogram::Histogram;
+use crate::import::Arc;
+use crate::spsc::{self, NoSpaceLeftError};
+use std::time::{Duration, Instant};
+
+/// Create a new wait-free queue with latency measurement. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::latency;
+///
+/// //                  Data type ──╮   ╭─ Capacity
+/// let (tx, rx) = latency::latency::<u64>(8);
+/// ```
+pub fn latency<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = spsc::spsc(capacity);
+    let histogram = Arc::new(Histogram::new());
+    (Sender { tx }, Receiver { rx, histogram })
+}
+
+/// The sending side of the [latency] queue.
+#[derive(Debug)]
+pub struct Sender<T> {
+    tx: spsc::Sender<(Instant, T)>,
+}
+
+impl<T> Sender<T> {
+    /// Attempts to send a value to the queue without blocking and stores the enqueue time.
+    /// Returns a [NoSpaceLeftError] if the queue is full.
+    pub fn try_send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.tx
+            .try_send((Instant::now(), data))
+            .map_err(|NoSpaceLeftError((_, data))| NoSpaceLeftError(data))
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.tx.capacity()
+    }
+}
+
+/// The receiving side of the [latency] queue.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    rx: spsc::Receiver<(Instant, T)>,
+    histogram: Arc<Histogram>,
+}
+
+impl<T> Receiver<T> {
+    /// Retrieve the next available element from the queue together with the time it spent in the queue.
+    /// The latency is recorded in nanoseconds in the [Histogram].
+    /// Returns [None] if the queue is empty.
+    pub fn try_recv(&mut self) -> Option<(T, Duration)> {
+        let (enqueued, data) = self.rx.try_recv()?;
+        let latency = enqueued.elapsed();
+        self.histogram
+            .record(u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX));
+        Some((data, latency))
+    }
+
+    /// Returns the [Histogram] of the latencies in nanoseconds, which can be read from another thread.
+    #[inline]
+    pub fn histogram(&self) -> Arc<Histogram> {
+        self.histogram.clone()
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.rx.capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut tx, mut rx) = latency(2);
+        tx.try_send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(NoSpaceLeftError(3)));
+        thread::sleep(Duration::from_millis(1));
+
+        let (msg, latency) = rx.try_recv().unwrap();
+        assert_eq!(msg, 1);
+        assert!(latency >= Duration::from_millis(1));
+        assert_eq!(rx.try_recv().unwrap().0, 2);
+        assert!(rx.try_recv().is_none());
+
+        let snapshot = rx.histogram().snapshot();
+        assert_eq!(snapshot.count(), 2);
+        assert!(snapshot.quantile(0.0) >= Some(1_000_000));
+        assert!(snapshot.max() >= Some(latency.as_nanos() as u64));
+    }
+
+    #[test]
+    fn test_empty() {
+        let (_, rx) = latency::<u8>(2);
+        let snapshot = rx.histogram().snapshot();
+        assert_eq!(snapshot.count(), 0);
+        assert_eq!(snapshot.quantile(0.99), None);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut tx, mut rx) = latency(8);
+        let histogram = rx.histogram();
+        let sender_thread = thread::spawn(move || {
+            let mut sent = 0;
+            while sent < 1000 {
+                if tx.try_send(sent).is_ok() {
+                    sent += 1;
+                }
+            }
+        });
+        let monitor_thread = thread::spawn(move || {
+            let mut last = 0;
+            while last < 1000 {
+                let count = histogram.snapshot().count();
+                assert!(count >= last);
+                last = count;
+            }
+        });
+        let mut received = 0;
+        while received < 1000 {
+            if let Some((msg, _)) = rx.try_recv() {
+                assert_eq!(msg, received);
+                received += 1;
+            }
+        }
+        assert!(sender_thread.join().is_ok());
+        assert!(monitor_thread.join().is_ok());
+    }
+}
@@ -3,16 +3,42 @@
 #[cfg(loom)]
 mod import {
     pub(crate) use loom::cell::UnsafeCell;
-    pub(crate) use loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    pub(crate) use loom::sync::atomic::{
+        AtomicBool, AtomicIsize, AtomicU64, AtomicUsize, Ordering,
+    };
     pub(crate) use loom::sync::Arc;
 }
 
 #[cfg(not(loom))]
 mod import {
     pub(crate) use core::cell::UnsafeCell;
-    pub(crate) use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    pub(crate) use core::sync::atomic::{
+        AtomicBool, AtomicIsize, AtomicU64, AtomicUsize, Ordering,
+    };
     pub(crate) use std::sync::Arc;
 }
 
+pub mod broadcast_buffer;
+pub mod broadcast_ring;
+pub mod conflate;
+pub mod duplex;
+pub mod garbage;
+pub mod histogram;
+pub mod history;
+pub mod latency;
+pub mod log;
+pub mod merge;
+pub mod mpmc;
+pub mod mpsc;
+pub mod oneshot;
+pub mod pool;
+pub mod priority;
+pub mod rcu;
+pub mod register;
+pub mod select;
+pub mod signal;
+pub mod spmc;
 pub mod spsc;
+#[cfg(feature = "stats")]
+pub mod stats;
 pub mod triple_buffer;
@@ -0,0 +1,373 @@
+//! A wait-free log channel, which keeps formatting and I/O out of real-time threads.
+//!
+//! The [Logger] does not format anything. It pushes a compact [Record] into a preallocated [spsc] queue,
+//! which consists of a static format string, up to [MAX_ARGS] plain arguments and a timestamp.
+//! A background thread drains the records with the [Drain], formats them and writes them out.
+//!
+//! The format string supports `{}` placeholders, which are replaced by the arguments in order,
+//! and `{{` and `}}` to write literal braces.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::log::{self, Level};
+//!
+//! let (mut logger, mut drain) = log::log(64);
+//! // In the real-time thread
+//! logger.try_log(Level::Warn, "cycle {} took {} us", &[42u64.into(), 1.5.into()]).unwrap();
+//!
+//! // In the background thread
+//! let record = drain.try_recv().unwrap();
+//! assert_eq!(record.to_string(), "cycle 42 took 1.5 us");
+//! ```
+
+use crate::spsc::{self, NoSpaceLeftError};
+use std::fmt;
+use std::io;
+use std::time::Instant;
+
+/// The maximum number of arguments of a [Record].
+pub const MAX_ARGS: usize = 4;
+
+/// Create a new wait-free log channel. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::log;
+///
+/// //                   Capacity ─╮
+/// let (logger, drain) = log::log(64);
+/// ```
+pub fn log(capacity: usize) -> (Logger, Drain) {
+    let (tx, rx) = spsc::spsc(capacity);
+    (
+        Logger { tx },
+        Drain {
+            rx,
+            start: Instant::now(),
+        },
+    )
+}
+
+/// The severity of a [Record].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Level {
+    /// A failure, which needs attention.
+    Error,
+    /// A problem, which was handled.
+    Warn,
+    /// A notable event.
+    Info,
+    /// Details for debugging.
+    Debug,
+    /// Very verbose details, e.g. of every cycle.
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.pad(name)
+    }
+}
+
+/// A plain argument of a [Record], which is formatted by the [Drain].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arg {
+    /// An unsigned integer.
+    U64(u64),
+    /// A signed integer.
+    I64(i64),
+    /// A floating point number.
+    F64(f64),
+    /// A boolean.
+    Bool(bool),
+    /// A character.
+    Char(char),
+    /// A static string.
+    Str(&'static str),
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::U64(val) => val.fmt(f),
+            Arg::I64(val) => val.fmt(f),
+            Arg::F64(val) => val.fmt(f),
+            Arg::Bool(val) => val.fmt(f),
+            Arg::Char(val) => val.fmt(f),
+            Arg::Str(val) => val.fmt(f),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($variant:ident, $target:ty, $($source:ty),*) => {
+        $(
+            impl From<$source> for Arg {
+                #[inline]
+                fn from(val: $source) -> Self {
+                    Arg::$variant(val as $target)
+                }
+            }
+        )*
+    };
+}
+impl_from!(U64, u64, u8, u16, u32, u64, usize);
+impl_from!(I64, i64, i8, i16, i32, i64, isize);
+impl_from!(F64, f64, f32, f64);
+impl_from!(Bool, bool, bool);
+impl_from!(Char, char, char);
+impl_from!(Str, &'static str, &'static str);
+
+/// A log record, which is created without formatting or allocation.
+#[derive(Clone, Debug)]
+pub struct Record {
+    timestamp: Instant,
+    level: Level,
+    format: &'static str,
+    args: [Arg; MAX_ARGS],
+    len: usize,
+    truncated: bool,
+}
+
+impl Record {
+    /// Returns the time when the record was logged.
+    #[inline]
+    pub fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+
+    /// Returns the severity of the record.
+    #[inline]
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Returns the format string of the record.
+    #[inline]
+    pub fn format(&self) -> &'static str {
+        self.format
+    }
+
+    /// Returns the arguments of the record.
+    #[inline]
+    pub fn args(&self) -> &[Arg] {
+        &self.args[..self.len]
+    }
+
+    /// Returns `true` if more than [MAX_ARGS] arguments were logged and the others were dropped.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+/// Formats the message of the record. Placeholders without an argument are written as is.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut args = self.args().iter();
+        let mut rest = self.format;
+        while let Some(pos) = rest.find(['{', '}']) {
+            f.write_str(&rest[..pos])?;
+            rest = &rest[pos..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                f.write_str(&rest[..1])?;
+                rest = &rest[2..];
+            } else if rest.starts_with("{}") {
+                match args.next() {
+                    Some(arg) => arg.fmt(f)?,
+                    None => f.write_str("{}")?,
+                }
+                rest = &rest[2..];
+            } else {
+                f.write_str(&rest[..1])?;
+                rest = &rest[1..];
+            }
+        }
+        f.write_str(rest)
+    }
+}
+
+/// The side of the [log] channel for the real-time thread.
+#[derive(Debug)]
+pub struct Logger {
+    tx: spsc::Sender<Record>,
+}
+
+impl Logger {
+    /// Attempts to log a record without formatting it.
+    /// Returns a [NoSpaceLeftError] with the record if the queue is full.
+    ///
+    /// Only the first [MAX_ARGS] arguments are kept, which is marked by [Record::is_truncated].
+    // Boxing the record would allocate in the real-time thread.
+    #[allow(clippy::result_large_err)]
+    pub fn try_log(
+        &mut self,
+        level: Level,
+        format: &'static str,
+        args: &[Arg],
+    ) -> Result<(), NoSpaceLeftError<Record>> {
+        let len = args.len().min(MAX_ARGS);
+        let mut record = Record {
+            timestamp: Instant::now(),
+            level,
+            format,
+            args: [Arg::U64(0); MAX_ARGS],
+            len,
+            truncated: args.len() > MAX_ARGS,
+        };
+        record.args[..len].copy_from_slice(&args[..len]);
+        self.tx.try_send(record)
+    }
+
+    /// Returns the total number of records that can be pending at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.tx.capacity()
+    }
+}
+
+/// The side of the [log] channel for the background thread, which formats the records.
+#[derive(Debug)]
+pub struct Drain {
+    rx: spsc::Receiver<Record>,
+    start: Instant,
+}
+
+impl Drain {
+    /// Retrieve the next record.
+    /// Returns [None] if there is no record.
+    #[inline]
+    pub fn try_recv(&mut self) -> Option<Record> {
+        self.rx.try_recv()
+    }
+
+    /// Formats all pending records and writes them line by line to `out`. Returns the number of records.
+    ///
+    /// Every line starts with the seconds since the channel was created and the level.
+    /// Records with dropped arguments end with `[truncated]`.
+    pub fn drain_to<W: io::Write>(&mut self, out: &mut W) -> io::Result<usize> {
+        let mut count = 0;
+        while let Some(record) = self.rx.try_recv() {
+            let time = record.timestamp.saturating_duration_since(self.start);
+            write!(
+                out,
+                "[{:>12.6}] {:<5} {}",
+                time.as_secs_f64(),
+                record.level,
+                record
+            )?;
+            if record.truncated {
+                write!(out, " [truncated]")?;
+            }
+            writeln!(out)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns the total number of records that can be pending at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.rx.capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut l, mut d) = log(4);
+        l.try_log(Level::Info, "no args", &[]).unwrap();
+        l.try_log(
+            Level::Error,
+            "{} {} {} {}",
+            &[1u8.into(), (-2i32).into(), true.into(), "str".into()],
+        )
+        .unwrap();
+
+        let record = d.try_recv().unwrap();
+        assert_eq!(record.level(), Level::Info);
+        assert_eq!(record.format(), "no args");
+        assert!(record.args().is_empty());
+        assert_eq!(record.to_string(), "no args");
+
+        let record = d.try_recv().unwrap();
+        assert_eq!(record.args()[1], Arg::I64(-2));
+        assert_eq!(record.to_string(), "1 -2 true str");
+        assert!(d.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_format() {
+        let (mut l, mut d) = log(4);
+        l.try_log(Level::Debug, "{{{}}} {} {x}", &['c'.into()])
+            .unwrap();
+        assert_eq!(d.try_recv().unwrap().to_string(), "{c} {} {x}");
+    }
+
+    #[test]
+    fn test_full() {
+        let (mut l, mut d) = log(2);
+        l.try_log(Level::Info, "a", &[]).unwrap();
+        l.try_log(Level::Info, "b", &[]).unwrap();
+        let err = l.try_log(Level::Info, "c", &[]).unwrap_err();
+        assert_eq!(err.0.format(), "c");
+        assert_eq!(d.drain_to(&mut io::sink()).unwrap(), 2);
+        assert!(l.try_log(Level::Info, "c", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_too_many_args() {
+        let (mut l, mut d) = log(2);
+        l.try_log(Level::Info, "{} {} {} {} {}", &[Arg::U64(7); MAX_ARGS + 1])
+            .unwrap();
+        let record = d.try_recv().unwrap();
+        assert!(record.is_truncated());
+        assert_eq!(record.args(), &[Arg::U64(7); MAX_ARGS]);
+        assert_eq!(record.to_string(), "7 7 7 7 {}");
+
+        l.try_log(Level::Info, "{}", &[Arg::U64(7); MAX_ARGS + 1])
+            .unwrap();
+        let mut out = Vec::new();
+        assert_eq!(d.drain_to(&mut out).unwrap(), 1);
+        assert!(String::from_utf8(out).unwrap().ends_with("7 [truncated]\n"));
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut l, mut d) = log(8);
+        let logger_thread = thread::spawn(move || {
+            let mut logged = 0u64;
+            while logged < 1000 {
+                if l.try_log(Level::Trace, "cycle {}", &[logged.into()])
+                    .is_ok()
+                {
+                    logged += 1;
+                }
+            }
+        });
+        let mut out = Vec::new();
+        let mut drained = 0;
+        while drained < 1000 {
+            drained += d.drain_to(&mut out).unwrap();
+        }
+        assert!(logger_thread.join().is_ok());
+
+        let out = String::from_utf8(out).unwrap();
+        for (i, line) in out.lines().enumerate() {
+            assert!(line.ends_with(&format!("] TRACE cycle {i}")));
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Wait-free fan-in of several [spsc] queues in global timestamp order, e.g. for sensor fusion.
+//!
+//! The [Merge] holds the next item of every input. It only yields the item with the smallest timestamp
+//! once every other input has either produced an item or been closed, so the order is deterministic
+//! as long as every input is sorted by timestamp. Equal timestamps are yielded in the order of the inputs.
+//!
+//! Since the [Merge] must not wait, [try_recv](Merge::try_recv) returns [None] as long as an open input is empty.
+//! A stalled input therefore stalls the whole merge.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::merge::{Merge, Timestamped};
+//! use waitfree_sync::spsc;
+//!
+//! #[derive(Debug)]
+//! struct Sample {
+//!     time: u64,
+//!     value: f64,
+//! }
+//!
+//! impl Timestamped for Sample {
+//!     type Timestamp = u64;
+//!
+//!     fn timestamp(&self) -> u64 {
+//!         self.time
+//!     }
+//! }
+//!
+//! let (mut tx_a, rx_a) = spsc::spsc(8);
+//! let (mut tx_b, rx_b) = spsc::spsc(8);
+//! let mut merge = Merge::new(vec![rx_a, rx_b]);
+//!
+//! tx_a.try_send(Sample { time: 2, value: 0.2 }).unwrap();
+//! // Input b may still produce an earlier sample.
+//! assert!(merge.try_recv().is_none());
+//!
+//! tx_b.try_send(Sample { time: 1, value: 0.1 }).unwrap();
+//! assert_eq!(merge.try_recv().unwrap().1.time, 1);
+//! drop(tx_b);
+//! assert_eq!(merge.try_recv().unwrap().1.time, 2);
+//! ```
+
+use crate::spsc;
+
+/// An item with a timestamp, by which the [Merge] orders the items.
+pub trait Timestamped {
+    /// The type of the timestamp, e.g. `u64` or `std::time::Instant`.
+    type Timestamp: Ord;
+
+    /// Returns the timestamp of the item.
+    fn timestamp(&self) -> Self::Timestamp;
+}
+
+#[derive(Debug)]
+struct Input<T> {
+    rx: spsc::Receiver<T>,
+    next: Option<T>,
+    exhausted: bool,
+}
+
+impl<T> Input<T> {
+    /// Fetches the next item if there is none yet.
+    /// Returns `false` if the input is open, but has no item yet.
+    #[inline]
+    fn fill(&mut self) -> bool {
+        if self.next.is_some() || self.exhausted {
+            return true;
+        }
+        // Check for closing first, so no item sent before can be missed.
+        let closed = self.rx.is_closed();
+        self.next = self.rx.try_recv();
+        self.exhausted = closed && self.next.is_none();
+        self.next.is_some() || self.exhausted
+    }
+}
+
+/// Merges several [spsc] queues by the [Timestamped::timestamp] of their items.
+#[derive(Debug)]
+pub struct Merge<T> {
+    inputs: Box<[Input<T>]>,
+}
+
+impl<T: Timestamped> Merge<T> {
+    /// Create a new merge of the given receivers. The index of a receiver is reported with its items.
+    /// All memory is allocated here, so receiving does not allocate.
+    pub fn new(receivers: Vec<spsc::Receiver<T>>) -> Self {
+        Merge {
+            inputs: receivers
+                .into_iter()
+                .map(|rx| Input {
+                    rx,
+                    next: None,
+                    exhausted: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Retrieve the item with the smallest timestamp together with the index of its input.
+    /// Returns [None] if an open input is empty, because it may still produce an earlier item,
+    /// or if all inputs are exhausted.
+    pub fn try_recv(&mut self) -> Option<(usize, T)> {
+        let mut ready = true;
+        for input in self.inputs.iter_mut() {
+            ready &= input.fill();
+        }
+        if !ready {
+            return None;
+        }
+
+        let mut earliest: Option<(usize, T::Timestamp)> = None;
+        for (idx, input) in self.inputs.iter().enumerate() {
+            if let Some(item) = &input.next {
+                let timestamp = item.timestamp();
+                if earliest.as_ref().map_or(true, |(_, min)| timestamp < *min) {
+                    earliest = Some((idx, timestamp));
+                }
+            }
+        }
+        let (idx, _) = earliest?;
+        self.inputs[idx].next.take().map(|item| (idx, item))
+    }
+
+    /// Returns `true` if all inputs are closed and all of their items were received.
+    pub fn is_finished(&mut self) -> bool {
+        self.inputs
+            .iter_mut()
+            .all(|input| input.fill() && input.next.is_none())
+    }
+
+    /// Returns the number of inputs.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns `true` if there are no inputs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[derive(Debug, PartialEq)]
+    struct Item(u64, usize);
+
+    impl Timestamped for Item {
+        type Timestamp = u64;
+
+        fn timestamp(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn smoke() {
+        let (mut tx_a, rx_a) = spsc::spsc(4);
+        let (mut tx_b, rx_b) = spsc::spsc(4);
+        let mut merge = Merge::new(vec![rx_a, rx_b]);
+        assert_eq!(merge.len(), 2);
+        assert_eq!(merge.try_recv(), None);
+
+        tx_a.try_send(Item(1, 0)).unwrap();
+        tx_a.try_send(Item(3, 0)).unwrap();
+        tx_b.try_send(Item(2, 1)).unwrap();
+        assert_eq!(merge.try_recv(), Some((0, Item(1, 0))));
+        assert_eq!(merge.try_recv(), Some((1, Item(2, 1))));
+        // Input b may still produce an item before 3.
+        assert_eq!(merge.try_recv(), None);
+        tx_b.try_send(Item(3, 1)).unwrap();
+        // Equal timestamps are yielded in the order of the inputs.
+        assert_eq!(merge.try_recv(), Some((0, Item(3, 0))));
+        assert_eq!(merge.try_recv(), None);
+
+        drop(tx_a);
+        assert!(!merge.is_finished());
+        assert_eq!(merge.try_recv(), Some((1, Item(3, 1))));
+        drop(tx_b);
+        assert_eq!(merge.try_recv(), None);
+        assert!(merge.is_finished());
+    }
+
+    #[test]
+    fn test_closed_with_items() {
+        let (mut tx_a, rx_a) = spsc::spsc(4);
+        let (tx_b, rx_b) = spsc::spsc::<Item>(4);
+        let mut merge = Merge::new(vec![rx_a, rx_b]);
+        tx_a.try_send(Item(5, 0)).unwrap();
+        tx_a.try_send(Item(6, 0)).unwrap();
+        drop(tx_a);
+        assert_eq!(merge.try_recv(), None);
+        drop(tx_b);
+        assert_eq!(merge.try_recv(), Some((0, Item(5, 0))));
+        assert_eq!(merge.try_recv(), Some((0, Item(6, 0))));
+        assert!(merge.is_finished());
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (receivers, handles): (Vec<_>, Vec<_>) = (0..3)
+            .map(|input| {
+                let (mut tx, rx) = spsc::spsc(4);
+                let handle = thread::spawn(move || {
+                    let mut time = input as u64;
+                    while time < 3000 {
+                        if tx.try_send(Item(time, input)).is_ok() {
+                            time += 3;
+                        }
+                    }
+                });
+                (rx, handle)
+            })
+            .unzip();
+        let mut merge = Merge::new(receivers);
+
+        let mut next = 0;
+        while !merge.is_finished() {
+            if let Some((input, item)) = merge.try_recv() {
+                assert_eq!(item, Item(next, input));
+                next += 1;
+            }
+        }
+        assert_eq!(next, 3000);
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+    }
+}
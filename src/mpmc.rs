@@ -0,0 +1,340 @@
+//! A wait-free multi-producer multi-consumer (MPMC) queue to pass data between several threads.
+//! Every item is delivered to exactly one [Receiver].
+//!
+//! Senders and receivers reserve space or items with a single atomic operation and then draw a ticket for their slot.
+//! Since senders and receivers can finish their slots out of order, the slots are published and released
+//! in order by advancing a frontier. Every finished operation helps to advance the frontier, which takes
+//! at most `2 * capacity` steps. Therefore, every operation completes in a bounded number of steps.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::mpmc;
+//!
+//! //                            Type ──╮   ╭─ Capacity
+//! let (mut tx, mut rx) = mpmc::mpmc::<u64>(8);
+//! let (mut tx2, mut rx2) = (tx.clone(), rx.clone());
+//! tx.try_send(1).unwrap();
+//! tx2.try_send(2).unwrap();
+//! assert_eq!(rx2.try_recv(), Some(1));
+//! assert_eq!(rx.try_recv(), Some(2));
+//! ```
+//!
+//! # Behavior for full and empty queue.
+//! If the queue is full, the [Sender] returns a [NoSpaceLeftError].
+//! If the queue is empty, the [Receiver] returns `None`.
+//!
+//! Since no operation waits for another one, the queue is also full while the oldest item is
+//! still being received, and empty while the oldest item is still being sent.
+
+use crate::import::{Arc, AtomicIsize, AtomicUsize, Ordering, UnsafeCell};
+use crate::spsc::is_power_of_two;
+pub use crate::spsc::NoSpaceLeftError;
+use crossbeam_utils::CachePadded;
+
+/// Create a new wait-free MPMC queue. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::mpmc;
+///
+/// //               Data type ──╮   ╭─ Capacity
+/// let (tx, rx) = mpmc::mpmc::<u64>(8);
+/// ```
+pub fn mpmc<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    if !is_power_of_two(capacity) {
+        panic!("The SIZE must be a power of 2")
+    }
+
+    let chan = Arc::new(Mpmc::new(capacity));
+
+    let r = Receiver::new(chan.clone());
+    let w = Sender::new(chan);
+
+    (w, r)
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    value: UnsafeCell<Option<T>>,
+    // For the ticket `t` of this slot, `t` means free, `t + 1` means written
+    // and `t + capacity` means received, which is free for the next round.
+    seq: CachePadded<AtomicUsize>,
+}
+
+#[derive(Debug)]
+struct Mpmc<T> {
+    mem: Box<[Slot<T>]>,
+    // Number of free slots, which are not yet reserved by a sender.
+    free: CachePadded<AtomicIsize>,
+    // Number of published items, which are not yet reserved by a receiver.
+    available: CachePadded<AtomicIsize>,
+    // Next ticket of the senders and receivers.
+    tail: CachePadded<AtomicUsize>,
+    head: CachePadded<AtomicUsize>,
+    // All slots below these tickets are published or released.
+    published: CachePadded<AtomicUsize>,
+    released: CachePadded<AtomicUsize>,
+    // The mask is written when this structure is created and is then only read.
+    // Therefore, we do not need Atomic here.
+    mask: usize,
+}
+
+impl<T> Mpmc<T> {
+    fn new(size: usize) -> Self {
+        let mut buffer = Vec::with_capacity(size);
+        for i in 0..size {
+            buffer.push(Slot {
+                value: UnsafeCell::new(None),
+                seq: CachePadded::new(AtomicUsize::new(i)),
+            });
+        }
+        let buffer: Box<[Slot<T>]> = buffer.into_boxed_slice();
+        Mpmc {
+            mem: buffer,
+            free: CachePadded::new(AtomicIsize::new(size as isize)),
+            available: CachePadded::new(0.into()),
+            tail: CachePadded::new(0.into()),
+            head: CachePadded::new(0.into()),
+            published: CachePadded::new(0.into()),
+            released: CachePadded::new(0.into()),
+            mask: size - 1,
+        }
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+
+    /// Advances the `frontier` over all slots in state `ticket + offset` and increments `counter` for each of them.
+    /// The helping stops at `limit`, which bounds the number of steps.
+    #[inline]
+    fn advance(&self, frontier: &AtomicUsize, offset: usize, counter: &AtomicIsize, limit: usize) {
+        // Read the frontier with a read-modify-write operation. Either this sees the frontier
+        // moved by a helper, or the helper sees the state of the slot which was just finished.
+        let mut pos = frontier.fetch_add(0, Ordering::AcqRel);
+        while pos < limit {
+            let slot = unsafe { self.mem.get_unchecked(pos & self.mask) };
+            if slot.seq.load(Ordering::Acquire) != pos + offset {
+                // The owner of this slot continues once it is done.
+                break;
+            }
+            match frontier.compare_exchange(pos, pos + 1, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => {
+                    counter.fetch_add(1, Ordering::AcqRel);
+                    pos += 1;
+                }
+                Err(current) => pos = current,
+            }
+        }
+    }
+}
+
+/// The receiving side of the [mpmc] queue. It can be cloned to receive in several threads.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    mpmc: Arc<Mpmc<T>>,
+}
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    fn new(mpmc: Arc<Mpmc<T>>) -> Self {
+        Receiver { mpmc }
+    }
+
+    /// Retrieve the next available element from the queue.
+    /// Returns [None] if the queue is empty.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let mpmc = &*self.mpmc;
+        if mpmc.available.fetch_sub(1, Ordering::AcqRel) <= 0 {
+            mpmc.available.fetch_add(1, Ordering::AcqRel);
+            return None;
+        }
+
+        // With a reservation, the item of this ticket has already been published.
+        let ticket = mpmc.head.fetch_add(1, Ordering::AcqRel);
+        let slot = unsafe { mpmc.mem.get_unchecked(ticket & mpmc.mask) };
+        debug_assert_eq!(slot.seq.load(Ordering::Acquire), ticket + 1);
+        #[cfg(not(loom))]
+        let val = unsafe { slot.value.get().replace(None) };
+        #[cfg(loom)]
+        let val = unsafe { slot.value.get_mut().with(|ptr| ptr.replace(None)) };
+
+        slot.seq.store(ticket + mpmc.capacity(), Ordering::Release);
+        mpmc.advance(
+            &mpmc.released,
+            mpmc.capacity(),
+            &mpmc.free,
+            ticket + mpmc.capacity(),
+        );
+        val
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.mpmc.capacity()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Receiver::new(self.mpmc.clone())
+    }
+}
+
+/// The sending side of the [mpmc] queue. It can be cloned to send from several threads.
+#[derive(Debug)]
+pub struct Sender<T> {
+    mpmc: Arc<Mpmc<T>>,
+}
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+
+impl<T> Sender<T> {
+    fn new(mpmc: Arc<Mpmc<T>>) -> Self {
+        Sender { mpmc }
+    }
+
+    /// Attempts to send a value to the queue without blocking.
+    /// Returns a [NoSpaceLeftError] if the queue is full.
+    pub fn try_send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        let mpmc = &*self.mpmc;
+        if mpmc.free.fetch_sub(1, Ordering::AcqRel) <= 0 {
+            mpmc.free.fetch_add(1, Ordering::AcqRel);
+            return Err(NoSpaceLeftError(data));
+        }
+
+        // With a reservation, the previous item of this slot has already been released.
+        let ticket = mpmc.tail.fetch_add(1, Ordering::AcqRel);
+        let slot = unsafe { mpmc.mem.get_unchecked(ticket & mpmc.mask) };
+        debug_assert_eq!(slot.seq.load(Ordering::Acquire), ticket);
+        #[cfg(not(loom))]
+        unsafe {
+            slot.value.get().write(Some(data))
+        };
+        #[cfg(loom)]
+        unsafe {
+            slot.value.get_mut().with(|ptr| ptr.write(Some(data)))
+        };
+
+        slot.seq.store(ticket + 1, Ordering::Release);
+        mpmc.advance(
+            &mpmc.published,
+            1,
+            &mpmc.available,
+            ticket + mpmc.capacity(),
+        );
+        Ok(())
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.mpmc.capacity()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender::new(self.mpmc.clone())
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = mpmc(4);
+        w.try_send(vec![0; 15]).unwrap();
+        w.clone().try_send(vec![0; 16]).unwrap();
+        w.try_send(vec![0; 17]).unwrap();
+        w.clone().try_send(vec![0; 18]).unwrap();
+
+        assert_eq!(r.try_recv(), Some(vec![0; 15]));
+        assert_eq!(r.clone().try_recv(), Some(vec![0; 16]));
+        assert_eq!(r.try_recv(), Some(vec![0; 17]));
+        assert_eq!(r.clone().try_recv(), Some(vec![0; 18]));
+    }
+
+    #[test]
+    fn test_full_empty() {
+        let (mut write, mut read) = mpmc::<i32>(4);
+        let (mut write2, mut read2) = (write.clone(), read.clone());
+        assert_eq!(read.try_recv(), None);
+        assert_eq!(write.try_send(1), Ok(()));
+        assert_eq!(write2.try_send(2), Ok(()));
+        assert_eq!(write.try_send(3), Ok(()));
+        assert_eq!(write2.try_send(4), Ok(()));
+        assert_eq!(write.try_send(5), Err(NoSpaceLeftError(5)));
+        assert_eq!(read2.try_recv(), Some(1));
+        assert_eq!(write2.try_send(6), Ok(()));
+        assert_eq!(write.try_send(7), Err(NoSpaceLeftError(7)));
+        assert_eq!(read.try_recv(), Some(2));
+        assert_eq!(read2.try_recv(), Some(3));
+        assert_eq!(read.try_recv(), Some(4));
+        assert_eq!(read2.try_recv(), Some(6));
+        assert_eq!(read.try_recv(), None);
+        assert_eq!(read2.try_recv(), None);
+    }
+
+    #[test]
+    fn test_multiple_threads() {
+        const THREADS: usize = 4;
+        const COUNT: usize = 2000;
+        let (w, r) = mpmc(16);
+        let senders: Vec<_> = (0..THREADS)
+            .map(|id| {
+                let mut w = w.clone();
+                thread::spawn(move || {
+                    for i in 0..COUNT {
+                        let mut val = (id, i);
+                        while let Err(NoSpaceLeftError(v)) = w.try_send(val) {
+                            val = v;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+        let receivers: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let mut r = r.clone();
+                thread::spawn(move || {
+                    let mut received = Vec::with_capacity(COUNT);
+                    while received.len() < COUNT {
+                        match r.try_recv() {
+                            Some(val) => received.push(val),
+                            None => thread::yield_now(),
+                        }
+                    }
+                    received
+                })
+            })
+            .collect();
+
+        for handle in senders {
+            assert!(handle.join().is_ok());
+        }
+        let mut all = Vec::with_capacity(THREADS * COUNT);
+        for handle in receivers {
+            let received = handle.join().unwrap();
+            // Each receiver sees the items of a sender in order.
+            for id in 0..THREADS {
+                let items: Vec<_> = received.iter().filter(|(i, _)| *i == id).collect();
+                assert!(items.windows(2).all(|pair| pair[0].1 < pair[1].1));
+            }
+            all.extend(received);
+        }
+        // Every item was delivered exactly once.
+        all.sort_unstable();
+        all.dedup();
+        assert_eq!(all.len(), THREADS * COUNT);
+    }
+}
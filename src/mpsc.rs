@@ -0,0 +1,271 @@
+//! A wait-free multi-producer single-consumer (MPSC) queue to send data from several threads to another thread.
+//!
+//! Every [Sender] first reserves space and then draws a ticket for a slot.
+//! Both steps are a single atomic operation, so sending completes in a bounded number of steps.
+//! The reservation guarantees that the slot of the ticket has already been freed by the [Receiver].
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::mpsc;
+//!
+//! //                            Type ──╮   ╭─ Capacity
+//! let (mut tx, mut rx) = mpsc::mpsc::<u64>(8);
+//! let mut tx2 = tx.clone();
+//! tx.try_send(1).unwrap();
+//! tx2.try_send(2).unwrap();
+//! assert_eq!(rx.try_recv(), Some(1));
+//! assert_eq!(rx.try_recv(), Some(2));
+//! ```
+//!
+//! # Behavior for full and empty queue.
+//! If the queue is full, the [Sender] returns a [NoSpaceLeftError].
+//! If the queue is empty, the [Receiver] returns `None`.
+//!
+//! Since the [Receiver] must not wait for a [Sender], it also returns `None` while the sender
+//! of the next item in line is still writing it, even if later items are already available.
+//! Likewise, concurrent senders may briefly see a full queue while another sender backs out
+//! of a failed reservation.
+
+use crate::import::{Arc, AtomicUsize, Ordering};
+pub use crate::spsc::NoSpaceLeftError;
+use crate::spsc::{is_power_of_two, Slot};
+use crossbeam_utils::CachePadded;
+
+/// Create a new wait-free MPSC queue. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::mpsc;
+///
+/// //               Data type ──╮   ╭─ Capacity
+/// let (tx, rx) = mpsc::mpsc::<u64>(8);
+/// ```
+pub fn mpsc<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    if !is_power_of_two(capacity) {
+        panic!("The SIZE must be a power of 2")
+    }
+
+    let chan = Arc::new(Mpsc::new(capacity));
+
+    let r = Receiver::new(chan.clone());
+    let w = Sender::new(chan);
+
+    (w, r)
+}
+
+#[derive(Debug)]
+struct Mpsc<T> {
+    mem: Box<[Slot<T>]>,
+    // Number of reserved slots, i.e. sent but not yet received items.
+    len: CachePadded<AtomicUsize>,
+    // Next ticket of the senders.
+    tail: CachePadded<AtomicUsize>,
+    // The mask is written when this structure is created and is then only read.
+    // Therefore, we do not need Atomic here.
+    mask: usize,
+}
+
+impl<T> Mpsc<T> {
+    fn new(size: usize) -> Self {
+        let mut buffer = Vec::with_capacity(size);
+        for _ in 0..size {
+            buffer.push(Slot::new());
+        }
+        let buffer: Box<[Slot<T>]> = buffer.into_boxed_slice();
+        Mpsc {
+            mem: buffer,
+            len: CachePadded::new(0.into()),
+            tail: CachePadded::new(0.into()),
+            mask: size - 1,
+        }
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+}
+
+/// The receiving side of the [mpsc] queue.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    mpsc: Arc<Mpsc<T>>,
+    read: usize,
+}
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    fn new(mpsc: Arc<Mpsc<T>>) -> Self {
+        Receiver { mpsc, read: 0 }
+    }
+
+    /// Retrieve the next available element from the queue.
+    /// Returns [None] if the queue is empty.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let rpos = self.read & self.mpsc.mask;
+        let slot = unsafe { self.mpsc.mem.get_unchecked(rpos) };
+        if !slot.occupied.load(Ordering::Acquire) {
+            None
+        } else {
+            #[cfg(not(loom))]
+            let val = unsafe { slot.value.get().replace(None) };
+            #[cfg(loom)]
+            let val = unsafe { slot.value.get_mut().with(|ptr| ptr.replace(None)) };
+
+            slot.occupied.store(false, Ordering::Release);
+            self.read += 1;
+            // Release the reservation only after the slot is free again.
+            self.mpsc.len.fetch_sub(1, Ordering::AcqRel);
+            val
+        }
+    }
+
+    /// Peeks the next element in the queue without removing it.
+    #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
+    pub fn peek(&self) -> Option<&T> {
+        let rpos = self.read & self.mpsc.mask;
+        let slot = unsafe { self.mpsc.mem.get_unchecked(rpos) };
+        if !slot.occupied.load(Ordering::Acquire) {
+            None
+        } else {
+            let val = unsafe { &*slot.value.get() };
+            val.as_ref()
+        }
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.mpsc.capacity()
+    }
+}
+
+/// The sending side of the [mpsc] queue. It can be cloned to send from several threads.
+#[derive(Debug)]
+pub struct Sender<T> {
+    mpsc: Arc<Mpsc<T>>,
+}
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+
+impl<T> Sender<T> {
+    fn new(mpsc: Arc<Mpsc<T>>) -> Self {
+        Sender { mpsc }
+    }
+
+    /// Attempts to send a value to the queue without blocking.
+    /// Returns a [NoSpaceLeftError] if the queue is full.
+    pub fn try_send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        let mpsc = &*self.mpsc;
+        if mpsc.len.fetch_add(1, Ordering::AcqRel) >= mpsc.capacity() {
+            mpsc.len.fetch_sub(1, Ordering::AcqRel);
+            return Err(NoSpaceLeftError(data));
+        }
+
+        // With a reservation, the previous item of this slot has already been received.
+        let wpos = mpsc.tail.fetch_add(1, Ordering::AcqRel) & mpsc.mask;
+        let slot = unsafe { mpsc.mem.get_unchecked(wpos) };
+        #[cfg(not(loom))]
+        unsafe {
+            slot.value.get().write(Some(data))
+        };
+        #[cfg(loom)]
+        unsafe {
+            slot.value.get_mut().with(|ptr| ptr.write(Some(data)))
+        };
+        slot.occupied.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.mpsc.capacity()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender::new(self.mpsc.clone())
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = mpsc(4);
+        w.try_send(vec![0; 15]).unwrap();
+        w.try_send(vec![0; 16]).unwrap();
+        w.clone().try_send(vec![0; 17]).unwrap();
+        w.clone().try_send(vec![0; 18]).unwrap();
+
+        assert_eq!(r.try_recv(), Some(vec![0; 15]));
+        assert_eq!(r.try_recv(), Some(vec![0; 16]));
+        assert_eq!(r.try_recv(), Some(vec![0; 17]));
+        assert_eq!(r.try_recv(), Some(vec![0; 18]));
+    }
+
+    #[test]
+    fn test_full_empty() {
+        let (mut write, mut read) = mpsc::<i32>(4);
+        let mut write2 = write.clone();
+        assert_eq!(write.try_send(1), Ok(()));
+        assert_eq!(write2.try_send(2), Ok(()));
+        assert_eq!(write.try_send(3), Ok(()));
+        assert_eq!(write2.try_send(4), Ok(()));
+        assert_eq!(write.try_send(5), Err(NoSpaceLeftError(5)));
+        assert_eq!(write2.try_send(5), Err(NoSpaceLeftError(5)));
+        assert_eq!(read.try_recv(), Some(1));
+        assert_eq!(write2.try_send(6), Ok(()));
+        assert_eq!(read.peek(), Some(&2));
+        assert_eq!(read.try_recv(), Some(2));
+        assert_eq!(read.try_recv(), Some(3));
+        assert_eq!(read.try_recv(), Some(4));
+        assert_eq!(read.try_recv(), Some(6));
+        assert_eq!(read.try_recv(), None);
+        assert_eq!(read.peek(), None);
+    }
+
+    #[test]
+    fn test_multiple_sender_threads() {
+        const SENDERS: usize = 4;
+        const COUNT: usize = 1000;
+        let (w, mut r) = mpsc(64);
+        let handles: Vec<_> = (0..SENDERS)
+            .map(|id| {
+                let mut w = w.clone();
+                thread::spawn(move || {
+                    for i in 0..COUNT {
+                        let mut val = (id, i);
+                        while let Err(NoSpaceLeftError(v)) = w.try_send(val) {
+                            val = v;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // Items of the same sender arrive in order.
+        let mut next = [0; SENDERS];
+        let mut received = 0;
+        while received < SENDERS * COUNT {
+            if let Some((id, i)) = r.try_recv() {
+                assert_eq!(next[id], i);
+                next[id] += 1;
+                received += 1;
+            }
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+        assert_eq!(r.try_recv(), None);
+    }
+}
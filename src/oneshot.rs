@@ -0,0 +1,193 @@
+//! A wait-free oneshot channel to send a single value to another thread, e.g. as a reply to a request.
+//!
+//! The whole state of the channel is tracked in a single atomic word.
+//! In contrast to a [spsc](crate::spsc) queue, the [Receiver] detects if the [Sender] was dropped without sending a value.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::oneshot;
+//!
+//! let (tx, mut rx) = oneshot::oneshot::<u64>();
+//! assert_eq!(rx.try_recv(), Ok(None));
+//! tx.send(234).unwrap();
+//! assert_eq!(rx.try_recv(), Ok(Some(234)));
+//! ```
+
+use crate::import::{Arc, AtomicUsize, Ordering, UnsafeCell};
+use core::error::Error;
+
+const SENT: usize = 0b001;
+const SENDER_DROPPED: usize = 0b010;
+const RECEIVER_DROPPED: usize = 0b100;
+
+/// Create a new wait-free oneshot channel.
+/// # Example
+/// ```rust
+/// use waitfree_sync::oneshot;
+///
+/// //               Data type ──╮
+/// let (tx, rx) = oneshot::oneshot::<u64>();
+/// ```
+pub fn oneshot<T>() -> (Sender<T>, Receiver<T>) {
+    let chan = Arc::new(Shared {
+        value: UnsafeCell::new(None),
+        state: AtomicUsize::new(0),
+    });
+
+    let r = Receiver {
+        shared: chan.clone(),
+        received: false,
+    };
+    let w = Sender { shared: chan };
+    (w, r)
+}
+
+/// Indicates that the [Sender] was dropped without sending a value or the value was already received.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisconnectedError;
+impl Error for DisconnectedError {}
+impl core::fmt::Display for DisconnectedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The oneshot channel is disconnected.")
+    }
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    // The value is dropped together with the channel if it was not received.
+    value: UnsafeCell<Option<T>>,
+    state: AtomicUsize,
+}
+
+/// The receiving side of the [oneshot] channel.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    received: bool,
+}
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    /// Retrieve the value if it was sent.
+    /// Returns `Ok(None)` if the value was not sent yet and a [DisconnectedError]
+    /// if the [Sender] was dropped without sending a value or the value was already received.
+    pub fn try_recv(&mut self) -> Result<Option<T>, DisconnectedError> {
+        if self.received {
+            return Err(DisconnectedError);
+        }
+        let state = self.shared.state.load(Ordering::Acquire);
+        if state & SENT > 0 {
+            self.received = true;
+            #[cfg(not(loom))]
+            let val = unsafe { self.shared.value.get().replace(None) };
+            #[cfg(loom)]
+            let val = unsafe { self.shared.value.get_mut().with(|ptr| ptr.replace(None)) };
+            Ok(val)
+        } else if state & SENDER_DROPPED > 0 {
+            Err(DisconnectedError)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .fetch_or(RECEIVER_DROPPED, Ordering::Release);
+    }
+}
+
+/// The sending side of the [oneshot] channel.
+#[derive(Debug)]
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+
+impl<T> Sender<T> {
+    /// Sends the value to the [Receiver].
+    /// Returns the value as error if the [Receiver] was already dropped.
+    pub fn send(self, data: T) -> Result<(), T> {
+        if self.is_closed() {
+            return Err(data);
+        }
+        #[cfg(not(loom))]
+        unsafe {
+            self.shared.value.get().write(Some(data))
+        };
+        #[cfg(loom)]
+        unsafe {
+            self.shared
+                .value
+                .get_mut()
+                .with(|ptr| ptr.write(Some(data)))
+        };
+        self.shared.state.fetch_or(SENT, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns `true` if the [Receiver] was dropped.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.shared.state.load(Ordering::Acquire) & RECEIVER_DROPPED > 0
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .fetch_or(SENDER_DROPPED, Ordering::Release);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (w, mut r) = oneshot();
+        assert_eq!(r.try_recv(), Ok(None));
+        w.send(vec![0; 15]).unwrap();
+        assert_eq!(r.try_recv(), Ok(Some(vec![0; 15])));
+        assert_eq!(r.try_recv(), Err(DisconnectedError));
+    }
+
+    #[test]
+    fn test_sender_dropped() {
+        let (w, mut r) = oneshot::<i32>();
+        drop(w);
+        assert_eq!(r.try_recv(), Err(DisconnectedError));
+    }
+
+    #[test]
+    fn test_receiver_dropped() {
+        let (w, r) = oneshot();
+        assert!(!w.is_closed());
+        drop(r);
+        assert!(w.is_closed());
+        assert_eq!(w.send(vec![0; 15]), Err(vec![0; 15]));
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (w, mut r) = oneshot();
+        let writer_thread = thread::spawn(move || {
+            w.send([7; 50]).unwrap();
+        });
+        let val = loop {
+            if let Some(val) = r.try_recv().unwrap() {
+                break val;
+            }
+        };
+        assert_eq!(val, [7; 50]);
+        assert!(writer_thread.join().is_ok());
+    }
+}
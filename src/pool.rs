@@ -0,0 +1,356 @@
+//! A wait-free pool of preallocated blocks shared between two threads.
+//!
+//! One thread allocates [Handle]s with the [Allocator] and another thread returns them with the [Deallocator].
+//! The indices of the blocks are kept in two [spsc] rings: The free ring holds the blocks which can be allocated
+//! and is only used by the [Allocator], while the return ring passes the returned blocks back to it.
+//! A handle only consists of a pointer and an index, so allocating and returning it does not touch a reference count.
+//! Instead of sending a `Box<T>`, which is freed by the receiving thread, large messages
+//! can be sent as small handles through a queue without any allocator traffic.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::{pool, spsc};
+//!
+//! let (mut alloc, mut dealloc) = pool::pool(4, || [0u8; 1024]);
+//! let (mut tx, mut rx) = spsc::spsc(4);
+//!
+//! let mut block = alloc.try_alloc().unwrap();
+//! block[0] = 42;
+//! tx.try_send(block).unwrap();
+//!
+//! let block = rx.try_recv().unwrap();
+//! assert_eq!(block[0], 42);
+//! dealloc.free(block);
+//! ```
+//!
+//! # Shutdown
+//! A [Handle] has no way back to its pool, so dropping it does not return its block.
+//! If a single handle is not returned before the [Allocator] and the [Deallocator] are dropped,
+//! the memory of all blocks is leaked and none of their values are dropped.
+//! This also happens when a queue carrying handles is dropped while it still holds some.
+//! Therefore every handle, including the ones in flight, must be returned before the pool goes away.
+
+use crate::import::{Arc, AtomicUsize, Ordering};
+use crate::spsc;
+// The blocks are synchronized by the rings of indices, so they don't need to be tracked by loom.
+use core::cell::UnsafeCell;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+/// Create a new wait-free pool with `capacity` blocks, which are initialized by `init`.
+/// The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::pool;
+///
+/// //                       Capacity ─╮  ╭─ Initial value of the blocks
+/// let (alloc, dealloc) = pool::pool(8, || 0u64);
+/// ```
+pub fn pool<T>(capacity: usize, mut init: impl FnMut() -> T) -> (Allocator<T>, Deallocator<T>) {
+    let (mut free_tx, free_rx) = spsc::spsc(capacity);
+    let (returned_tx, returned_rx) = spsc::spsc(capacity);
+    for index in 0..capacity {
+        let _ = free_tx.try_send(index);
+    }
+    let blocks = Arc::new(Blocks {
+        mem: ManuallyDrop::new((0..capacity).map(|_| UnsafeCell::new(init())).collect()),
+        outstanding: AtomicUsize::new(0),
+    });
+
+    let a = Allocator {
+        blocks: blocks.clone(),
+        free_tx,
+        free_rx,
+        returned: returned_rx,
+        outstanding: 0,
+    };
+    let d = Deallocator {
+        blocks,
+        returned: returned_tx,
+        freed: 0,
+    };
+    (a, d)
+}
+
+#[derive(Debug)]
+struct Blocks<T> {
+    mem: ManuallyDrop<Box<[UnsafeCell<T>]>>,
+    // The number of handles which were not returned, summed up when the allocator and the deallocator are dropped.
+    outstanding: AtomicUsize,
+}
+
+impl<T> Blocks<T> {
+    #[inline]
+    fn handle(&self, index: usize) -> Handle<T> {
+        Handle {
+            // SAFETY: The pointer of an UnsafeCell is never null.
+            block: unsafe { NonNull::new_unchecked(self.mem[index].get()) },
+            index,
+        }
+    }
+
+    /// Returns `true` if the handle belongs to this pool.
+    #[inline]
+    fn owns(&self, handle: &Handle<T>) -> bool {
+        self.mem
+            .get(handle.index)
+            .map_or(false, |block| block.get() == handle.block.as_ptr())
+    }
+}
+
+impl<T> Drop for Blocks<T> {
+    fn drop(&mut self) {
+        // A handle which was not returned may still be in use, so its block must stay valid.
+        if self.outstanding.load(Ordering::Acquire) == 0 {
+            unsafe { ManuallyDrop::drop(&mut self.mem) };
+        }
+    }
+}
+
+/// A block of the [pool]. It can be sent to another thread and gives exclusive access to the block.
+///
+/// A handle must be returned with [Deallocator::free] or [Allocator::free], otherwise the block is lost for the pool.
+/// Since such a handle could still be in use, the memory of all blocks is then leaked when the pool is dropped,
+/// see [Shutdown](crate::pool#shutdown).
+#[derive(Debug)]
+#[must_use = "the block is lost for the pool if the handle is not returned"]
+pub struct Handle<T> {
+    block: NonNull<T>,
+    index: usize,
+}
+unsafe impl<T: Send> Send for Handle<T> {}
+unsafe impl<T: Sync> Sync for Handle<T> {}
+
+impl<T> Deref for Handle<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // SAFETY: The handle is the only owner of the block, and the blocks are not freed while it exists.
+        unsafe { self.block.as_ref() }
+    }
+}
+
+impl<T> DerefMut for Handle<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The handle is the only owner of the block, and the blocks are not freed while it exists.
+        unsafe { self.block.as_mut() }
+    }
+}
+
+/// The allocating side of the [pool].
+#[derive(Debug)]
+pub struct Allocator<T> {
+    blocks: Arc<Blocks<T>>,
+    free_tx: spsc::Sender<usize>,
+    free_rx: spsc::Receiver<usize>,
+    returned: spsc::Receiver<usize>,
+    // The number of allocated handles minus the ones returned with `Allocator::free`.
+    outstanding: usize,
+}
+unsafe impl<T: Send> Send for Allocator<T> {}
+unsafe impl<T: Send> Sync for Allocator<T> {}
+
+impl<T> Allocator<T> {
+    /// Allocates a block from the pool.
+    /// Returns [None] if all blocks are in use.
+    ///
+    /// The block keeps the value it had when it was returned.
+    pub fn try_alloc(&mut self) -> Option<Handle<T>> {
+        let index = match self.free_rx.try_recv() {
+            Some(index) => index,
+            None => self.returned.try_recv()?,
+        };
+        self.outstanding = self.outstanding.wrapping_add(1);
+        Some(self.blocks.handle(index))
+    }
+
+    /// Returns a block to the pool from the allocating thread, e.g. if it could not be sent.
+    /// The value of the block is not dropped.
+    /// # Panic
+    /// Panics if the handle belongs to another pool.
+    pub fn free(&mut self, handle: Handle<T>) {
+        assert!(
+            self.blocks.owns(&handle),
+            "The handle belongs to another pool"
+        );
+        // There are never more handles than slots in the ring.
+        let _ = self.free_tx.try_send(handle.index);
+        self.outstanding = self.outstanding.wrapping_sub(1);
+    }
+
+    /// Returns the total number of blocks in the pool.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.blocks.mem.len()
+    }
+}
+
+impl<T> Drop for Allocator<T> {
+    fn drop(&mut self) {
+        self.blocks
+            .outstanding
+            .fetch_add(self.outstanding, Ordering::AcqRel);
+    }
+}
+
+/// The returning side of the [pool].
+#[derive(Debug)]
+pub struct Deallocator<T> {
+    blocks: Arc<Blocks<T>>,
+    returned: spsc::Sender<usize>,
+    freed: usize,
+}
+unsafe impl<T: Send> Send for Deallocator<T> {}
+unsafe impl<T: Send> Sync for Deallocator<T> {}
+
+impl<T> Deallocator<T> {
+    /// Returns a block to the pool. The value of the block is not dropped.
+    /// # Panic
+    /// Panics if the handle belongs to another pool.
+    pub fn free(&mut self, handle: Handle<T>) {
+        assert!(
+            self.blocks.owns(&handle),
+            "The handle belongs to another pool"
+        );
+        // There are never more handles than slots in the ring.
+        let _ = self.returned.try_send(handle.index);
+        self.freed = self.freed.wrapping_add(1);
+    }
+
+    /// Returns the total number of blocks in the pool.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.blocks.mem.len()
+    }
+}
+
+impl<T> Drop for Deallocator<T> {
+    fn drop(&mut self) {
+        self.blocks
+            .outstanding
+            .fetch_sub(self.freed, Ordering::AcqRel);
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut a, mut d) = pool(2, Vec::<u8>::new);
+        let mut h1 = a.try_alloc().unwrap();
+        let mut h2 = a.try_alloc().unwrap();
+        assert!(a.try_alloc().is_none());
+        h1.push(1);
+        h2.push(2);
+        assert_eq!(*h1, vec![1]);
+        assert_eq!(*h2, vec![2]);
+
+        d.free(h2);
+        let h3 = a.try_alloc().unwrap();
+        // The block keeps its value.
+        assert_eq!(*h3, vec![2]);
+        assert!(a.try_alloc().is_none());
+        d.free(h1);
+        d.free(h3);
+        assert!(a.try_alloc().is_some());
+        assert!(a.try_alloc().is_some());
+        assert!(a.try_alloc().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_foreign_handle() {
+        let (mut a, _) = pool(2, || 0u8);
+        let (_, mut d) = pool(2, || 0u8);
+        d.free(a.try_alloc().unwrap());
+    }
+
+    #[test]
+    fn test_free_on_allocator() {
+        let (mut a, _) = pool(2, || 0u8);
+        let h1 = a.try_alloc().unwrap();
+        let h2 = a.try_alloc().unwrap();
+        assert!(a.try_alloc().is_none());
+        a.free(h1);
+        a.free(h2);
+        assert!(a.try_alloc().is_some());
+        assert!(a.try_alloc().is_some());
+        assert!(a.try_alloc().is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // The blocks are leaked on purpose.
+    fn test_leak() {
+        let (mut a, d) = pool(2, || vec![1u8]);
+        let h1 = a.try_alloc().unwrap();
+        let mut h2 = a.try_alloc().unwrap();
+        // A dropped handle is lost for the pool.
+        drop(h1);
+        assert!(a.try_alloc().is_none());
+
+        // The blocks stay valid for handles which outlive the pool.
+        drop(a);
+        drop(d);
+        h2.push(2);
+        assert_eq!(*h2, vec![1, 2]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // The blocks are leaked on purpose.
+    fn test_dropped_queue() {
+        let value = Arc::new(0u8);
+        let (mut a, mut d) = pool(2, || value.clone());
+        let (mut tx, mut rx) = spsc::spsc(2);
+        tx.try_send(a.try_alloc().unwrap()).unwrap();
+        tx.try_send(a.try_alloc().unwrap()).unwrap();
+        d.free(rx.try_recv().unwrap());
+
+        // The queue still holds a handle, which is dropped with it and lost for the pool.
+        drop(tx);
+        drop(rx);
+        assert!(a.try_alloc().is_some());
+        assert!(a.try_alloc().is_none());
+
+        // The values of the blocks are leaked with the pool.
+        drop(a);
+        drop(d);
+        assert_eq!(Arc::strong_count(&value), 3);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut a, mut d) = pool(8, || [0usize; 64]);
+        let (mut tx, mut rx) = spsc::spsc(8);
+
+        let receiver_thread = thread::spawn(move || {
+            let mut received = 0;
+            while received < 1000 {
+                if let Some(block) = rx.try_recv() {
+                    let block: Handle<[usize; 64]> = block;
+                    assert!(block.iter().all(|entry| *entry == block[0]));
+                    d.free(block);
+                    received += 1;
+                }
+            }
+        });
+
+        let mut sent = 0;
+        while sent < 1000 {
+            if let Some(mut block) = a.try_alloc() {
+                block.fill(sent);
+                assert!(tx.try_send(block).is_ok());
+                sent += 1;
+            }
+        }
+        assert!(receiver_thread.join().is_ok());
+    }
+}
@@ -0,0 +1,183 @@
+//! A wait-free single-producer single-consumer channel with several priority lanes.
+//!
+//! Every lane is an [spsc] queue with its own capacity, and lane `0` has the highest priority.
+//! The [Receiver] always returns an item of the highest non-empty lane, so urgent items,
+//! like an emergency stop, never wait behind a full lane of bulk items.
+//! Items are in order within a lane, but not across lanes.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::priority;
+//!
+//! //                        Capacity of every lane ──╮
+//! let (mut tx, mut rx) = priority::priority::<&str>(&[2, 8]);
+//! tx.try_send(1, "update").unwrap();
+//! tx.try_send(0, "stop").unwrap();
+//! assert_eq!(rx.try_recv(), Some("stop"));
+//! assert_eq!(rx.try_recv(), Some("update"));
+//! ```
+
+use crate::spsc::{self, NoSpaceLeftError};
+
+/// Create a new wait-free priority channel with one lane per entry in `capacities`.
+/// Lane `0` has the highest priority. Every capacity must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if there are no lanes or a capacity is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::priority;
+///
+/// //                      Data type ──╮      ╭─ Capacities of the lanes
+/// let (tx, rx) = priority::priority::<u64>(&[4, 64]);
+/// ```
+pub fn priority<T>(capacities: &[usize]) -> (Sender<T>, Receiver<T>) {
+    assert!(!capacities.is_empty(), "There must be at least one lane");
+    let (lanes_tx, lanes_rx) = capacities
+        .iter()
+        .map(|capacity| spsc::spsc(*capacity))
+        .unzip();
+    (Sender { lanes: lanes_tx }, Receiver { lanes: lanes_rx })
+}
+
+/// The sending side of the [priority] channel.
+#[derive(Debug)]
+pub struct Sender<T> {
+    lanes: Vec<spsc::Sender<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Attempts to send a value to the given lane without blocking.
+    /// Returns a [NoSpaceLeftError] if the lane is full.
+    /// # Panic
+    /// Panics if the lane does not exist.
+    #[inline]
+    pub fn try_send(&mut self, lane: usize, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.lanes[lane].try_send(data)
+    }
+
+    /// Returns the number of lanes.
+    #[inline]
+    pub fn lanes(&self) -> usize {
+        self.lanes.len()
+    }
+
+    /// Returns the total number of items that the given lane can hold at most.
+    /// # Panic
+    /// Panics if the lane does not exist.
+    #[inline]
+    pub fn capacity(&self, lane: usize) -> usize {
+        self.lanes[lane].capacity()
+    }
+}
+
+/// The receiving side of the [priority] channel.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    lanes: Vec<spsc::Receiver<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Retrieve the next available element of the highest non-empty lane.
+    /// Returns [None] if all lanes are empty.
+    #[inline]
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.lanes.iter_mut().find_map(|lane| lane.try_recv())
+    }
+
+    /// Retrieve the next available element of the given lane.
+    /// Returns [None] if the lane is empty.
+    /// # Panic
+    /// Panics if the lane does not exist.
+    #[inline]
+    pub fn try_recv_lane(&mut self, lane: usize) -> Option<T> {
+        self.lanes[lane].try_recv()
+    }
+
+    /// Returns the number of lanes.
+    #[inline]
+    pub fn lanes(&self) -> usize {
+        self.lanes.len()
+    }
+
+    /// Returns the total number of items that the given lane can hold at most.
+    /// # Panic
+    /// Panics if the lane does not exist.
+    #[inline]
+    pub fn capacity(&self, lane: usize) -> usize {
+        self.lanes[lane].capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut tx, mut rx) = priority(&[2, 4, 8]);
+        assert_eq!(tx.lanes(), 3);
+        assert_eq!(rx.capacity(1), 4);
+        tx.try_send(2, 1).unwrap();
+        tx.try_send(1, 2).unwrap();
+        tx.try_send(2, 3).unwrap();
+        tx.try_send(0, 4).unwrap();
+        assert_eq!(rx.try_recv(), Some(4));
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.try_recv(), Some(1));
+        tx.try_send(1, 5).unwrap();
+        assert_eq!(rx.try_recv(), Some(5));
+        assert_eq!(rx.try_recv(), Some(3));
+        assert_eq!(rx.try_recv(), None);
+    }
+
+    #[test]
+    fn test_full_lane() {
+        let (mut tx, mut rx) = priority(&[2, 2]);
+        tx.try_send(1, 1).unwrap();
+        tx.try_send(1, 2).unwrap();
+        assert_eq!(tx.try_send(1, 3), Err(NoSpaceLeftError(3)));
+        // A full lane does not block the other lanes.
+        tx.try_send(0, 4).unwrap();
+        assert_eq!(rx.try_recv_lane(1), Some(1));
+        assert_eq!(rx.try_recv(), Some(4));
+        assert_eq!(rx.try_recv(), Some(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_lanes() {
+        let _ = priority::<u8>(&[]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_lane() {
+        let (mut tx, _) = priority(&[2]);
+        let _ = tx.try_send(1, 0u8);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut tx, mut rx) = priority(&[2, 8]);
+        let sender_thread = thread::spawn(move || {
+            let mut sent = [0; 2];
+            while sent.iter().any(|sent| *sent < 1000) {
+                for (lane, sent) in sent.iter_mut().enumerate() {
+                    if *sent < 1000 && tx.try_send(lane, (lane, *sent)).is_ok() {
+                        *sent += 1;
+                    }
+                }
+            }
+        });
+        let mut next = [0; 2];
+        while next.iter().any(|next| *next < 1000) {
+            if let Some((lane, val)) = rx.try_recv() {
+                assert_eq!(val, next[lane]);
+                next[lane] += 1;
+            }
+        }
+        assert!(sender_thread.join().is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+//! Wait-free publication of `Arc<T>` snapshots to several readers, e.g. for large immutable configurations.
+//!
+//! In contrast to the [broadcast_buffer](crate::broadcast_buffer), the readers don't clone the value,
+//! but get a reference to the current version. It uses the same slots with `N + 2` `Arc`s for `N` readers.
+//! Only the [Writer] replaces old versions, so they are never dropped by a reader.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use waitfree_sync::rcu;
+//!
+//! let (mut wr, mut readers) = rcu::rcu(Arc::new(vec![1, 2, 3]), 2);
+//! assert_eq!(readers[0].load(), &vec![1, 2, 3]);
+//! wr.store(Arc::new(vec![4, 5, 6]));
+//! assert_eq!(readers[1].load(), &vec![4, 5, 6]);
+//! ```
+
+use crate::broadcast_buffer::Shared;
+use crate::import::Arc;
+use std::sync::Arc as StdArc;
+
+/// Create a new wait-free RCU cell with an initial version and a fixed number of readers.
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use waitfree_sync::rcu;
+///
+/// //                         Initial version ──╮    ╭─ Number of readers
+/// let (wr, readers) = rcu::rcu(Arc::new(String::from("config")), 3);
+/// ```
+pub fn rcu<T>(initial: StdArc<T>, readers: usize) -> (Writer<T>, Vec<Reader<T>>) {
+    let chan = Arc::new(Shared::new(readers, |_| initial.clone()));
+
+    let r = (0..readers)
+        .map(|id| Reader {
+            shared: chan.clone(),
+            id,
+        })
+        .collect();
+    let w = Writer {
+        in_use: vec![false; chan.mem.len()].into_boxed_slice(),
+        shared: chan,
+    };
+    (w, r)
+}
+
+/// One of the reading sides of the [rcu] cell.
+#[derive(Debug)]
+pub struct Reader<T> {
+    shared: Arc<Shared<StdArc<T>>>,
+    id: usize,
+}
+unsafe impl<T: Send + Sync> Send for Reader<T> {}
+unsafe impl<T: Send + Sync> Sync for Reader<T> {}
+
+impl<T> Reader<T> {
+    /// Returns a reference to the current version without copying it.
+    /// The version is kept alive until the next call.
+    #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
+    #[inline]
+    pub fn load(&mut self) -> &T {
+        let idx = self.shared.claim(self.id);
+        unsafe { &*self.shared.mem[idx].get() }
+    }
+
+    /// Returns the current version as a new `Arc`.
+    /// If the reader holds the last reference, dropping it also drops the version.
+    #[inline]
+    pub fn load_full(&mut self) -> StdArc<T> {
+        let idx = self.shared.claim(self.id);
+
+        #[cfg(loom)]
+        let val = unsafe { self.shared.mem[idx].get().deref() }.clone();
+        #[cfg(not(loom))]
+        let val = unsafe { &*self.shared.mem[idx].get() }.clone();
+        val
+    }
+}
+
+/// The writing side of the [rcu] cell.
+#[derive(Debug)]
+pub struct Writer<T> {
+    shared: Arc<Shared<StdArc<T>>>,
+    // Scratch memory to find a free slot without allocation.
+    in_use: Box<[bool]>,
+}
+unsafe impl<T: Send + Sync> Send for Writer<T> {}
+unsafe impl<T: Send + Sync> Sync for Writer<T> {}
+
+impl<T> Writer<T> {
+    /// Returns the number of readers.
+    #[inline]
+    pub fn readers(&self) -> usize {
+        self.shared.readers()
+    }
+
+    /// Publishes a new version.
+    #[inline]
+    pub fn store(&mut self, value: StdArc<T>) {
+        // Drop old version
+        let _ = self.swap(value);
+    }
+
+    /// Publishes a new version and returns the old version of the reused slot instead of dropping it.
+    /// This allows to drop it in another thread, e.g. with the [garbage](crate::garbage) module.
+    #[inline]
+    pub fn swap(&mut self, value: StdArc<T>) -> StdArc<T> {
+        let idx = self.shared.free_slot(&mut self.in_use);
+
+        #[cfg(loom)]
+        let old = unsafe {
+            self.shared.mem[idx]
+                .get_mut()
+                .with(|ptr| ptr.replace(value))
+        };
+        #[cfg(not(loom))]
+        let old = unsafe { self.shared.mem[idx].get().replace(value) };
+
+        self.shared.publish(idx);
+        old
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = rcu(StdArc::new(vec![0; 15]), 2);
+        assert_eq!(r[0].load(), &vec![0; 15]);
+        w.store(StdArc::new(vec![1; 15]));
+        for r in r.iter_mut() {
+            assert_eq!(r.load(), &vec![1; 15]);
+            assert_eq!(*r.load_full(), vec![1; 15]);
+        }
+    }
+
+    #[test]
+    fn test_old_versions_dropped_by_writer() {
+        let (mut w, mut r) = rcu(StdArc::new(0), 1);
+        let first = StdArc::new(1);
+        w.store(first.clone());
+        assert_eq!(*r[0].load(), 1);
+        for i in 2..10 {
+            w.store(StdArc::new(i));
+        }
+        // The reader still holds the first version.
+        assert_eq!(StdArc::strong_count(&first), 2);
+        assert_eq!(*r[0].load(), 9);
+        w.store(StdArc::new(10));
+        w.store(StdArc::new(11));
+        // The writer released the first version.
+        assert_eq!(StdArc::strong_count(&first), 1);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (mut w, r) = rcu(StdArc::new([0usize; 64]), 3);
+        let handles: Vec<_> = r
+            .into_iter()
+            .map(|mut r| {
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..10_000 {
+                        let val = r.load();
+                        assert!(val.iter().all(|entry| *entry == val[0]));
+                        // Versions never go back in time.
+                        assert!(val[0] >= last);
+                        last = val[0];
+                    }
+                })
+            })
+            .collect();
+        for i in 0..10_000 {
+            w.store(StdArc::new([i; 64]));
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Wait-free multi-producer single-consumer (MPSC) register to share the latest value of several writers with one thread.
+//!
+//! This is the multi-writer counterpart to the [triple_buffer](crate::triple_buffer).
+//! With `N` writers, `N + 2` slots are used. Every party owns exactly one slot and the latest value
+//! is exchanged with a single atomic swap. Therefore, the reader always sees the most recently completed write.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::register;
+//!
+//! let (mut writers, mut rd) = register::register(2);
+//! writers[0].write(1);
+//! writers[1].write(2);
+//! assert_eq!(rd.try_read(), Some(2));
+//! ```
+
+use crate::import::{Arc, AtomicUsize, Ordering, UnsafeCell};
+use crossbeam_utils::CachePadded;
+
+const NEW_DATA_FLAG: usize = 1 << (usize::BITS - 1);
+const INDEX_MASK: usize = !NEW_DATA_FLAG;
+
+#[derive(Debug)]
+struct Shared<T> {
+    mem: Box<[UnsafeCell<Option<T>>]>,
+    latest: CachePadded<AtomicUsize>,
+}
+
+impl<T> Shared<T> {
+    fn new(writers: usize) -> Self {
+        let mem = (0..writers + 2).map(|_| UnsafeCell::new(None)).collect();
+        Shared {
+            mem,
+            latest: CachePadded::new(0.into()),
+        }
+    }
+}
+
+/// Create a new wait-free register with a fixed number of writers.
+/// # Example
+/// ```rust
+/// use waitfree_sync::register;
+///
+/// //               Data type ──╮   ╭─ Number of writers
+/// let (writers, rd) = register::register::<u64>(3);
+/// assert_eq!(writers.len(), 3);
+/// ```
+pub fn register<T>(writers: usize) -> (Vec<Writer<T>>, Reader<T>) {
+    let chan = Arc::new(Shared::new(writers));
+
+    // Slot 0 is the latest one and slot 1 belongs to the reader.
+    let w = (0..writers)
+        .map(|id| Writer::new(chan.clone(), id + 2))
+        .collect();
+    let r = Reader::new(chan);
+    (w, r)
+}
+
+/// The reading side of the [register].
+#[derive(Debug)]
+pub struct Reader<T> {
+    shared: Arc<Shared<T>>,
+    read_idx: usize,
+}
+unsafe impl<T: Send> Send for Reader<T> {}
+unsafe impl<T: Send> Sync for Reader<T> {}
+
+impl<T> Reader<T> {
+    fn new(shared: Arc<Shared<T>>) -> Self {
+        Reader {
+            shared,
+            read_idx: 1,
+        }
+    }
+
+    /// Reads the latest available value.
+    /// Returns [None] if no [Writer] has written anything yet.
+    #[inline]
+    pub fn try_read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let has_new_data = self.shared.latest.load(Ordering::Acquire) & NEW_DATA_FLAG > 0;
+        if has_new_data {
+            self.read_idx = self.shared.latest.swap(self.read_idx, Ordering::AcqRel) & INDEX_MASK;
+        }
+
+        #[cfg(loom)]
+        let val = unsafe { self.shared.mem[self.read_idx].get().deref() }.clone();
+        #[cfg(not(loom))]
+        let val = unsafe { &*self.shared.mem[self.read_idx].get() }.clone();
+        val
+    }
+}
+
+/// One of the writing sides of the [register].
+#[derive(Debug)]
+pub struct Writer<T> {
+    shared: Arc<Shared<T>>,
+    write_idx: usize,
+}
+unsafe impl<T: Send> Send for Writer<T> {}
+unsafe impl<T: Send> Sync for Writer<T> {}
+
+impl<T> Writer<T> {
+    fn new(shared: Arc<Shared<T>>, write_idx: usize) -> Self {
+        Writer { shared, write_idx }
+    }
+
+    /// Writes an new value.
+    #[inline]
+    pub fn write(&mut self, data: T) {
+        #[cfg(loom)]
+        unsafe {
+            self.shared.mem[self.write_idx].get_mut().with(|ptr| {
+                let _ = ptr.replace(Some(data));
+            });
+        }
+        #[cfg(not(loom))]
+        // Drop old value and write new one
+        let _ = unsafe { self.shared.mem[self.write_idx].get().replace(Some(data)) };
+
+        // Take over the previous latest slot, which is not owned by anybody else.
+        self.write_idx = self
+            .shared
+            .latest
+            .swap(self.write_idx | NEW_DATA_FLAG, Ordering::AcqRel)
+            & INDEX_MASK;
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = register(1);
+        w[0].write(vec![0; 15]);
+
+        assert_eq!(r.try_read(), Some(vec![0; 15]));
+    }
+
+    #[test]
+    fn test_read_none() {
+        let (mut w, mut r) = register(2);
+        assert_eq!(r.try_read(), None);
+        w[1].write(1);
+        assert_eq!(r.try_read(), Some(1));
+        assert_eq!(r.try_read(), Some(1));
+    }
+
+    #[test]
+    fn test_latest_write_wins() {
+        let (mut w, mut r) = register(3);
+        w[0].write(1);
+        w[2].write(2);
+        w[1].write(3);
+        assert_eq!(r.try_read(), Some(3));
+        w[1].write(4);
+        w[0].write(5);
+        assert_eq!(r.try_read(), Some(5));
+    }
+
+    #[test]
+    fn test_multiple_writer_threads() {
+        let (writers, mut r) = register(3);
+        let handles: Vec<_> = writers
+            .into_iter()
+            .enumerate()
+            .map(|(id, mut w)| {
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        w.write([id * 1000 + i; 20]);
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..1000 {
+            if let Some(val) = r.try_read() {
+                assert!(val.iter().all(|entry| *entry == val[0]));
+            }
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Wait-free polling of many receivers, e.g. of all input queues of a controller thread.
+//!
+//! A [Select] owns several [Source]s and polls them in [Order::RoundRobin] or [Order::Priority] order.
+//! Every call polls each source at most once, so it completes in a bounded number of steps.
+//! Receivers of different types can be combined with [Source::map] into a common enum.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::select::{Order, Select, Source};
+//! use waitfree_sync::spsc;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Input {
+//!     Setpoint(f64),
+//!     Stop,
+//! }
+//!
+//! let (mut tx_a, rx_a) = spsc::spsc(8);
+//! let (mut tx_b, rx_b) = spsc::spsc(8);
+//!
+//! let mut select: Select<Box<dyn Source<Item = Input>>> = Select::new(Order::RoundRobin);
+//! let setpoints = select.add(Box::new(rx_a.map(Input::Setpoint)));
+//! let stops = select.add(Box::new(rx_b.map(|()| Input::Stop)));
+//!
+//! tx_a.try_send(1.5).unwrap();
+//! tx_b.try_send(()).unwrap();
+//! assert_eq!(select.try_recv(), Some((setpoints, Input::Setpoint(1.5))));
+//! assert_eq!(select.try_recv(), Some((stops, Input::Stop)));
+//! assert_eq!(select.try_recv(), None);
+//! ```
+
+use crate::spsc;
+
+/// A receiver which can be polled by a [Select].
+pub trait Source {
+    /// The type of the received items.
+    type Item;
+
+    /// Retrieve the next available item. Returns [None] if there is no item.
+    fn try_recv(&mut self) -> Option<Self::Item>;
+
+    /// Converts the received items with `f`, e.g. into a variant of a common enum.
+    fn map<U, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> U,
+    {
+        Map { source: self, f }
+    }
+}
+
+impl<T> Source for spsc::Receiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn try_recv(&mut self) -> Option<T> {
+        spsc::Receiver::try_recv(self)
+    }
+}
+
+impl<S: Source + ?Sized> Source for Box<S> {
+    type Item = S::Item;
+
+    #[inline]
+    fn try_recv(&mut self) -> Option<S::Item> {
+        (**self).try_recv()
+    }
+}
+
+/// A [Source] which converts the items of another source. It is created by [Source::map].
+#[derive(Debug)]
+pub struct Map<S, F> {
+    source: S,
+    f: F,
+}
+
+impl<S: Source, U, F: FnMut(S::Item) -> U> Source for Map<S, F> {
+    type Item = U;
+
+    #[inline]
+    fn try_recv(&mut self) -> Option<U> {
+        self.source.try_recv().map(&mut self.f)
+    }
+}
+
+/// The order in which a [Select] polls its sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Continues after the source which yielded last, so every source gets its turn.
+    RoundRobin,
+    /// Always starts with the first added source, so earlier sources are preferred.
+    Priority,
+}
+
+/// Polls several [Source]s and reports which one yielded.
+#[derive(Debug)]
+pub struct Select<S> {
+    sources: Vec<S>,
+    order: Order,
+    next: usize,
+}
+
+impl<S: Source> Select<S> {
+    /// Create a new empty select with the given polling [Order].
+    pub fn new(order: Order) -> Self {
+        Select {
+            sources: Vec::new(),
+            order,
+            next: 0,
+        }
+    }
+
+    /// Adds a source and returns its index, which is reported with its items.
+    /// This may allocate, so it should be done before the real-time loop.
+    pub fn add(&mut self, source: S) -> usize {
+        self.sources.push(source);
+        self.sources.len() - 1
+    }
+
+    /// Retrieve the next available item together with the index of its source.
+    /// Every source is polled at most once. Returns [None] if no source has an item.
+    pub fn try_recv(&mut self) -> Option<(usize, S::Item)> {
+        let len = self.sources.len();
+        let start = match self.order {
+            Order::RoundRobin => self.next,
+            Order::Priority => 0,
+        };
+        for offset in 0..len {
+            let idx = (start + offset) % len;
+            if let Some(item) = self.sources[idx].try_recv() {
+                self.next = (idx + 1) % len;
+                return Some((idx, item));
+            }
+        }
+        None
+    }
+
+    /// Returns a mutable reference to the source with the given index.
+    #[inline]
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut S> {
+        self.sources.get_mut(idx)
+    }
+
+    /// Returns the number of sources.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Returns `true` if there are no sources.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let mut select = Select::new(Order::RoundRobin);
+        assert!(select.is_empty());
+        assert_eq!(select.try_recv(), None);
+
+        let (mut tx, rx) = spsc::spsc(4);
+        assert_eq!(select.add(rx), 0);
+        assert_eq!(select.len(), 1);
+        tx.try_send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(select.try_recv(), Some((0, 1)));
+        assert_eq!(select.try_recv(), Some((0, 2)));
+        assert_eq!(select.try_recv(), None);
+        assert!(select.get_mut(0).is_some());
+        assert!(select.get_mut(1).is_none());
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut select = Select::new(Order::RoundRobin);
+        let mut senders: Vec<_> = (0..3)
+            .map(|_| {
+                let (mut tx, rx) = spsc::spsc(4);
+                select.add(rx);
+                for i in 0..2 {
+                    tx.try_send(i).unwrap();
+                }
+                tx
+            })
+            .collect();
+        let order: Vec<_> = core::iter::from_fn(|| select.try_recv()).collect();
+        assert_eq!(order, vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+
+        // The source after the last yielding one is polled first.
+        senders[0].try_send(2).unwrap();
+        senders[2].try_send(2).unwrap();
+        assert_eq!(select.try_recv(), Some((0, 2)));
+        senders[0].try_send(3).unwrap();
+        assert_eq!(select.try_recv(), Some((2, 2)));
+        assert_eq!(select.try_recv(), Some((0, 3)));
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut select = Select::new(Order::Priority);
+        let (mut tx_high, rx_high) = spsc::spsc(4);
+        let (mut tx_low, rx_low) = spsc::spsc(4);
+        select.add(rx_high);
+        select.add(rx_low);
+
+        tx_low.try_send(1).unwrap();
+        tx_low.try_send(2).unwrap();
+        tx_high.try_send(3).unwrap();
+        assert_eq!(select.try_recv(), Some((0, 3)));
+        assert_eq!(select.try_recv(), Some((1, 1)));
+        tx_high.try_send(4).unwrap();
+        assert_eq!(select.try_recv(), Some((0, 4)));
+        assert_eq!(select.try_recv(), Some((1, 2)));
+        assert_eq!(select.try_recv(), None);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let mut select = Select::new(Order::RoundRobin);
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let (mut tx, rx) = spsc::spsc(4);
+                select.add(rx);
+                thread::spawn(move || {
+                    let mut sent = 0;
+                    while sent < 1000 {
+                        if tx.try_send(sent).is_ok() {
+                            sent += 1;
+                        }
+                    }
+                })
+            })
+            .collect();
+        let mut next = [0; 3];
+        while next.iter().any(|next| *next < 1000) {
+            if let Some((idx, val)) = select.try_recv() {
+                assert_eq!(val, next[idx]);
+                next[idx] += 1;
+            }
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! A wait-free set of up to 64 event flags to signal events like "config changed" or "shutdown" to another thread.
+//!
+//! In contrast to sending `()` through a [spsc](crate::spsc) queue, raising a flag never fails.
+//! Raising the same flag several times before it is taken signals the event only once.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::signal;
+//!
+//! const CONFIG_CHANGED: u64 = 1 << 0;
+//! const SHUTDOWN: u64 = 1 << 1;
+//!
+//! let (mut tx, mut rx) = signal::signal();
+//! tx.raise(CONFIG_CHANGED);
+//! tx.raise(CONFIG_CHANGED | SHUTDOWN);
+//! assert_eq!(rx.take(), CONFIG_CHANGED | SHUTDOWN);
+//! assert_eq!(rx.take(), 0);
+//! ```
+
+use crate::import::{Arc, AtomicU64, Ordering};
+use crossbeam_utils::CachePadded;
+
+/// Create a new wait-free signal set.
+/// # Example
+/// ```rust
+/// use waitfree_sync::signal;
+///
+/// let (tx, rx) = signal::signal();
+/// ```
+pub fn signal() -> (Sender, Receiver) {
+    let chan = Arc::new(CachePadded::new(AtomicU64::new(0)));
+
+    let r = Receiver {
+        pending: chan.clone(),
+    };
+    let w = Sender { pending: chan };
+    (w, r)
+}
+
+/// The raising side of the [signal] set. It can be cloned to raise flags from several threads.
+#[derive(Debug, Clone)]
+pub struct Sender {
+    pending: Arc<CachePadded<AtomicU64>>,
+}
+
+impl Sender {
+    /// Raises all flags which are set in `flags`.
+    #[inline]
+    pub fn raise(&mut self, flags: u64) {
+        self.pending.fetch_or(flags, Ordering::Release);
+    }
+}
+
+/// The taking side of the [signal] set.
+#[derive(Debug)]
+pub struct Receiver {
+    pending: Arc<CachePadded<AtomicU64>>,
+}
+
+impl Receiver {
+    /// Takes all pending flags and clears them.
+    #[inline]
+    pub fn take(&mut self) -> u64 {
+        self.pending.swap(0, Ordering::Acquire)
+    }
+
+    /// Takes only the pending flags which are set in `mask` and clears them.
+    /// The other flags stay pending.
+    #[inline]
+    pub fn take_masked(&mut self, mask: u64) -> u64 {
+        self.pending.fetch_and(!mask, Ordering::Acquire) & mask
+    }
+
+    /// Returns the pending flags without clearing them.
+    #[inline]
+    pub fn peek(&self) -> u64 {
+        self.pending.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = signal();
+        assert_eq!(r.take(), 0);
+        w.raise(0b101);
+        w.clone().raise(0b110);
+        assert_eq!(r.peek(), 0b111);
+        assert_eq!(r.take(), 0b111);
+        assert_eq!(r.take(), 0);
+    }
+
+    #[test]
+    fn test_take_masked() {
+        let (mut w, mut r) = signal();
+        w.raise(1 << 63 | 0b11);
+        assert_eq!(r.take_masked(0b10), 0b10);
+        assert_eq!(r.take_masked(0b10), 0);
+        assert_eq!(r.take(), 1 << 63 | 0b01);
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (w, mut r) = signal();
+        let handles: Vec<_> = (0..4)
+            .map(|bit| {
+                let mut w = w.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        w.raise(1 << bit);
+                    }
+                })
+            })
+            .collect();
+        let mut seen = 0;
+        while seen != 0b1111 {
+            seen |= r.take();
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+        assert_eq!(seen, 0b1111);
+    }
+}
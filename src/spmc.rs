@@ -0,0 +1,264 @@
+//! A wait-free single-producer multi-consumer (SPMC) queue to distribute data from one thread to several threads.
+//! Every item is delivered to exactly one [Receiver].
+//!
+//! The [Sender] works like the one of the [spsc](crate::spsc) queue and is wait-free.
+//! Every [Receiver] first reserves one of the published items and then draws a ticket for its slot.
+//! Both steps are a single atomic operation, so receiving completes in a bounded number of steps.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::spmc;
+//!
+//! //                            Type ──╮   ╭─ Capacity
+//! let (mut tx, mut rx) = spmc::spmc::<u64>(8);
+//! let mut rx2 = rx.clone();
+//! tx.try_send(1).unwrap();
+//! tx.try_send(2).unwrap();
+//! assert_eq!(rx2.try_recv(), Some(1));
+//! assert_eq!(rx.try_recv(), Some(2));
+//! ```
+//!
+//! # Behavior for full and empty queue.
+//! If the queue is full, the [Sender] returns a [NoSpaceLeftError].
+//! If the queue is empty, the [Receiver] returns `None`.
+//!
+//! Since the [Sender] must not wait for a [Receiver], the queue is also full while the receiver
+//! of the oldest slot is still taking its item out.
+//! Likewise, concurrent receivers may briefly see an empty queue while another receiver backs out
+//! of a failed reservation.
+
+use crate::import::{Arc, AtomicIsize, AtomicUsize, Ordering};
+pub use crate::spsc::NoSpaceLeftError;
+use crate::spsc::{is_power_of_two, Slot};
+use crossbeam_utils::CachePadded;
+
+/// Create a new wait-free SPMC queue. The `capacity` must be a power of two, which is validate during runtime.
+/// # Panic
+/// Panics if the `capacity` is not a power of two.
+/// # Example
+/// ```rust
+/// use waitfree_sync::spmc;
+///
+/// //               Data type ──╮   ╭─ Capacity
+/// let (tx, rx) = spmc::spmc::<u64>(8);
+/// ```
+pub fn spmc<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    if !is_power_of_two(capacity) {
+        panic!("The SIZE must be a power of 2")
+    }
+
+    let chan = Arc::new(Spmc::new(capacity));
+
+    let r = Receiver::new(chan.clone());
+    let w = Sender::new(chan);
+
+    (w, r)
+}
+
+#[derive(Debug)]
+struct Spmc<T> {
+    mem: Box<[Slot<T>]>,
+    // Number of published items, which are not yet reserved by a receiver.
+    available: CachePadded<AtomicIsize>,
+    // Next ticket of the receivers.
+    head: CachePadded<AtomicUsize>,
+    // The mask is written when this structure is created and is then only read.
+    // Therefore, we do not need Atomic here.
+    mask: usize,
+}
+
+impl<T> Spmc<T> {
+    fn new(size: usize) -> Self {
+        let mut buffer = Vec::with_capacity(size);
+        for _ in 0..size {
+            buffer.push(Slot::new());
+        }
+        let buffer: Box<[Slot<T>]> = buffer.into_boxed_slice();
+        Spmc {
+            mem: buffer,
+            available: CachePadded::new(0.into()),
+            head: CachePadded::new(0.into()),
+            mask: size - 1,
+        }
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+}
+
+/// The receiving side of the [spmc] queue. It can be cloned to receive in several threads.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    spmc: Arc<Spmc<T>>,
+}
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    fn new(spmc: Arc<Spmc<T>>) -> Self {
+        Receiver { spmc }
+    }
+
+    /// Retrieve the next available element from the queue.
+    /// Returns [None] if the queue is empty.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let spmc = &*self.spmc;
+        if spmc.available.fetch_sub(1, Ordering::AcqRel) <= 0 {
+            spmc.available.fetch_add(1, Ordering::AcqRel);
+            return None;
+        }
+
+        // With a reservation, the item of this slot has already been published.
+        let rpos = spmc.head.fetch_add(1, Ordering::AcqRel) & spmc.mask;
+        let slot = unsafe { spmc.mem.get_unchecked(rpos) };
+        #[cfg(not(loom))]
+        let val = unsafe { slot.value.get().replace(None) };
+        #[cfg(loom)]
+        let val = unsafe { slot.value.get_mut().with(|ptr| ptr.replace(None)) };
+
+        slot.occupied.store(false, Ordering::Release);
+        val
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.spmc.capacity()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Receiver::new(self.spmc.clone())
+    }
+}
+
+/// The sending side of the [spmc] queue.
+#[derive(Debug)]
+pub struct Sender<T> {
+    spmc: Arc<Spmc<T>>,
+    write: usize,
+}
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+
+impl<T> Sender<T> {
+    fn new(spmc: Arc<Spmc<T>>) -> Self {
+        Sender { spmc, write: 0 }
+    }
+
+    /// Attempts to send a value to the queue without blocking.
+    /// Returns a [NoSpaceLeftError] if the queue is full.
+    pub fn try_send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        let wpos = self.write & self.spmc.mask;
+
+        let slot = unsafe { self.spmc.mem.get_unchecked(wpos) };
+        if slot.occupied.load(Ordering::Acquire) {
+            Err(NoSpaceLeftError(data))
+        } else {
+            #[cfg(not(loom))]
+            unsafe {
+                slot.value.get().write(Some(data))
+            };
+            #[cfg(loom)]
+            unsafe {
+                slot.value.get_mut().with(|ptr| ptr.write(Some(data)))
+            };
+            slot.occupied.store(true, Ordering::Release);
+            self.write += 1;
+            // Publish the item to the receivers.
+            self.spmc.available.fetch_add(1, Ordering::AcqRel);
+            Ok(())
+        }
+    }
+
+    /// Returns the total number of items that the queue can hold at most.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.spmc.capacity()
+    }
+}
+
+#[cfg(not(loom))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (mut w, mut r) = spmc(4);
+        w.try_send(vec![0; 15]).unwrap();
+        w.try_send(vec![0; 16]).unwrap();
+        w.try_send(vec![0; 17]).unwrap();
+        w.try_send(vec![0; 18]).unwrap();
+
+        assert_eq!(r.try_recv(), Some(vec![0; 15]));
+        assert_eq!(r.clone().try_recv(), Some(vec![0; 16]));
+        assert_eq!(r.try_recv(), Some(vec![0; 17]));
+        assert_eq!(r.clone().try_recv(), Some(vec![0; 18]));
+    }
+
+    #[test]
+    fn test_full_empty() {
+        let (mut write, mut read) = spmc::<i32>(4);
+        let mut read2 = read.clone();
+        assert_eq!(read.try_recv(), None);
+        assert_eq!(write.try_send(1), Ok(()));
+        assert_eq!(write.try_send(2), Ok(()));
+        assert_eq!(write.try_send(3), Ok(()));
+        assert_eq!(write.try_send(4), Ok(()));
+        assert_eq!(write.try_send(5), Err(NoSpaceLeftError(5)));
+        assert_eq!(read2.try_recv(), Some(1));
+        assert_eq!(write.try_send(6), Ok(()));
+        assert_eq!(read.try_recv(), Some(2));
+        assert_eq!(read2.try_recv(), Some(3));
+        assert_eq!(read.try_recv(), Some(4));
+        assert_eq!(read2.try_recv(), Some(6));
+        assert_eq!(read.try_recv(), None);
+        assert_eq!(read2.try_recv(), None);
+    }
+
+    #[test]
+    fn test_multiple_receiver_threads() {
+        const RECEIVERS: usize = 4;
+        const COUNT: usize = 4000;
+        let (mut w, r) = spmc(64);
+        let sum = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..RECEIVERS)
+            .map(|_| {
+                let mut r = r.clone();
+                let sum = sum.clone();
+                let received = received.clone();
+                thread::spawn(move || {
+                    let mut last = None;
+                    while received.load(Ordering::Acquire) < COUNT {
+                        if let Some(i) = r.try_recv() {
+                            // Each receiver sees the items in order.
+                            assert!(last < Some(i));
+                            last = Some(i);
+                            sum.fetch_add(i, Ordering::AcqRel);
+                            received.fetch_add(1, Ordering::AcqRel);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..COUNT {
+            while w.try_send(i).is_err() {
+                thread::yield_now();
+            }
+        }
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+        // Every item was delivered exactly once.
+        assert_eq!(received.load(Ordering::Acquire), COUNT);
+        assert_eq!(sum.load(Ordering::Acquire), COUNT * (COUNT - 1) / 2);
+    }
+}
@@ -14,10 +14,21 @@
 //! # Behavior for full and empty queue.
 //! If the queue is full, the [Sender] returns a [NoSpaceLeftError].
 //! If the queue is empty, the [Receiver] returns `None`
+//!
+//! # Zero-sized types
+//! A queue of a zero-sized type like `()` does not allocate any slots.
+//! It collapses to a wait-free counter of pending messages, e.g. for tick or credit signals.
+//!
+//! # Closing
+//! Dropping the [Sender] closes the queue, which the [Receiver] can check with [Receiver::is_closed].
+//! Items sent before closing are still received.
 
 //!
-use crate::import::{Arc, AtomicBool, Ordering, UnsafeCell};
+use crate::import::{Arc, AtomicBool, AtomicUsize, Ordering, UnsafeCell};
+#[cfg(feature = "stats")]
+use crate::stats::{QueueCounters, QueueStats};
 use core::error::Error;
+use core::ptr::NonNull;
 use crossbeam_utils::CachePadded;
 use std::fmt::Debug;
 
@@ -44,28 +55,35 @@ pub fn spsc<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
     (w, r)
 }
 
-const fn is_power_of_two(x: usize) -> bool {
+pub(crate) const fn is_power_of_two(x: usize) -> bool {
     let c = x.wrapping_sub(1);
     (x != 0) && (x != 1) && ((x & c) == 0)
 }
 
 /// Indicates that a queue is full.
 #[derive(Clone, Debug, PartialEq)]
-pub struct NoSpaceLeftError<T>(T);
+pub struct NoSpaceLeftError<T>(pub(crate) T);
+impl<T> NoSpaceLeftError<T> {
+    /// Returns the value which could not be sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
 impl<T: Debug> Error for NoSpaceLeftError<T> {}
 impl<T> core::fmt::Display for NoSpaceLeftError<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "No space left in the SPSC queue.")
+        write!(f, "No space left in the queue.")
     }
 }
 
 #[derive(Debug)]
-struct Slot<T> {
-    value: UnsafeCell<Option<T>>,
-    occupied: CachePadded<AtomicBool>,
+pub(crate) struct Slot<T> {
+    pub(crate) value: UnsafeCell<Option<T>>,
+    pub(crate) occupied: CachePadded<AtomicBool>,
 }
 impl<T> Slot<T> {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             value: UnsafeCell::new(None),
             occupied: CachePadded::new(false.into()),
@@ -79,18 +97,31 @@ struct Spsc<T> {
     // The mask is written when this structure is created and is then only read.
     // Therefore, we do not need Atomic here.
     mask: usize,
+    // Number of pending messages, which replaces the slots for zero-sized types.
+    pending: CachePadded<AtomicUsize>,
+    // Set once when the sender is dropped.
+    closed: AtomicBool,
+    #[cfg(feature = "stats")]
+    stats: CachePadded<QueueCounters>,
 }
 
 impl<T> Spsc<T> {
+    const IS_ZST: bool = size_of::<T>() == 0;
+
     fn new(size: usize) -> Self {
-        let mut buffer = Vec::with_capacity(size);
-        for _ in 0..size {
+        let slots = if Self::IS_ZST { 0 } else { size };
+        let mut buffer = Vec::with_capacity(slots);
+        for _ in 0..slots {
             buffer.push(Slot::new());
         }
         let buffer: Box<[Slot<T>]> = buffer.into_boxed_slice();
         Spsc {
             mem: buffer,
             mask: size - 1,
+            pending: CachePadded::new(AtomicUsize::new(0)),
+            closed: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            stats: CachePadded::default(),
         }
     }
 
@@ -98,6 +129,42 @@ impl<T> Spsc<T> {
     fn capacity(&self) -> usize {
         self.mask + 1
     }
+
+    /// Counts a zero-sized message instead of storing it.
+    #[inline]
+    fn try_send_zst(&self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        // Only the receiver decrements the counter in the meantime.
+        if self.pending.load(Ordering::Acquire) == self.capacity() {
+            Err(NoSpaceLeftError(data))
+        } else {
+            core::mem::forget(data);
+            self.pending.fetch_add(1, Ordering::Release);
+            Ok(())
+        }
+    }
+
+    /// Recreates a counted zero-sized message.
+    #[inline]
+    fn try_recv_zst(&self) -> Option<T> {
+        // Only the sender increments the counter in the meantime.
+        if self.pending.load(Ordering::Acquire) == 0 {
+            None
+        } else {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+            // SAFETY: A dangling pointer is valid for zero-sized reads, and the type is inhabited
+            // because a value was sent.
+            Some(unsafe { NonNull::<T>::dangling().as_ptr().read() })
+        }
+    }
+}
+
+impl<T> Drop for Spsc<T> {
+    fn drop(&mut self) {
+        if Self::IS_ZST {
+            // Drop the pending zero-sized messages like the slots drop their values.
+            while self.try_recv_zst().is_some() {}
+        }
+    }
 }
 
 /// The receiving side of the [spsc] queue.
@@ -119,6 +186,19 @@ impl<T> Receiver<T> {
     /// Retrieve the next available element from the queue.
     /// Returns [None] if the queue is empty.
     pub fn try_recv(&mut self) -> Option<T> {
+        let val = self.recv();
+        #[cfg(feature = "stats")]
+        if val.is_some() {
+            self.spsc.stats.record_recv();
+        }
+        val
+    }
+
+    #[inline]
+    fn recv(&mut self) -> Option<T> {
+        if Spsc::<T>::IS_ZST {
+            return self.spsc.try_recv_zst();
+        }
         let rpos = self.read & self.spsc.mask;
         let slot = unsafe { self.spsc.mem.get_unchecked(rpos) };
         if !slot.occupied.load(Ordering::Acquire) {
@@ -134,9 +214,24 @@ impl<T> Receiver<T> {
             val
         }
     }
+    /// Returns `true` if the [Sender] was dropped.
+    /// Items sent before are still received, so the queue is exhausted once it is closed and
+    /// [try_recv](Receiver::try_recv) returns [None] afterwards.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.spsc.closed.load(Ordering::Acquire)
+    }
+
     /// Peeks the next element in the queue without removing it.
     #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
     pub fn peek(&self) -> Option<&T> {
+        if Spsc::<T>::IS_ZST {
+            return match self.spsc.pending.load(Ordering::Acquire) {
+                0 => None,
+                // SAFETY: A dangling pointer is valid for zero-sized references.
+                _ => Some(unsafe { NonNull::<T>::dangling().as_ref() }),
+            };
+        }
         let rpos = self.read & self.spsc.mask;
         let slot = unsafe { self.spsc.mem.get_unchecked(rpos) };
         if !slot.occupied.load(Ordering::Acquire) {
@@ -152,6 +247,13 @@ impl<T> Receiver<T> {
         // SAFETY: This is safe because we only read size which is never written.
         self.spsc.capacity()
     }
+
+    /// Returns a snapshot of the counters of the queue.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> QueueStats {
+        self.spsc.stats.snapshot()
+    }
 }
 
 /// The sending side of the [spsc] queue.
@@ -168,10 +270,27 @@ impl<T> Sender<T> {
     }
 }
 
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.spsc.closed.store(true, Ordering::Release);
+    }
+}
+
 impl<T> Sender<T> {
     /// Attempts to send a value to the queue without blocking.
     /// Returns a [NoSpaceLeftError] if the queue is full.
     pub fn try_send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        let res = self.send(data);
+        #[cfg(feature = "stats")]
+        self.spsc.stats.record_send(res.is_ok(), self.capacity());
+        res
+    }
+
+    #[inline]
+    fn send(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        if Spsc::<T>::IS_ZST {
+            return self.spsc.try_send_zst(data);
+        }
         let wpos = self.write & self.spsc.mask;
 
         let slot = unsafe { self.spsc.mem.get_unchecked(wpos) };
@@ -198,6 +317,13 @@ impl<T> Sender<T> {
         // SAFETY: This is safe because we only read size which is never written.
         self.spsc.capacity()
     }
+
+    /// Returns a snapshot of the counters of the queue.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> QueueStats {
+        self.spsc.stats.snapshot()
+    }
 }
 
 #[cfg(not(loom))]
@@ -263,6 +389,152 @@ mod test {
         assert_eq!(read.try_recv(), None);
     }
 
+    #[test]
+    fn test_zero_sized() {
+        let (mut write, mut read) = spsc::<()>(4);
+        assert!(write.spsc.mem.is_empty());
+        assert_eq!(read.try_recv(), None);
+        assert_eq!(read.peek(), None);
+        for _ in 0..4 {
+            assert_eq!(write.try_send(()), Ok(()));
+        }
+        assert_eq!(write.try_send(()), Err(NoSpaceLeftError(())));
+        assert_eq!(read.peek(), Some(&()));
+        assert_eq!(read.try_recv(), Some(()));
+        assert_eq!(write.try_send(()), Ok(()));
+        for _ in 0..4 {
+            assert_eq!(read.try_recv(), Some(()));
+        }
+        assert_eq!(read.try_recv(), None);
+    }
+
+    #[test]
+    fn test_zero_sized_drop() {
+        use std::sync::atomic::AtomicUsize;
+        static DROPPED: AtomicUsize = AtomicUsize::new(0);
+        #[derive(Debug)]
+        struct Token;
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let (mut write, mut read) = spsc::<Token>(4);
+        write.try_send(Token).unwrap();
+        write.try_send(Token).unwrap();
+        write.try_send(Token).unwrap();
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 0);
+        drop(read.try_recv());
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+        drop(write);
+        drop(read);
+        // The pending tokens are dropped with the queue.
+        assert_eq!(DROPPED.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_zero_sized_threaded() {
+        let (mut sender, mut receiver) = spsc::<()>(4);
+        let writer_thread = thread::spawn(move || {
+            let mut sent = 0;
+            while sent < 1000 {
+                if sender.try_send(()).is_ok() {
+                    sent += 1;
+                }
+            }
+        });
+        let mut received = 0;
+        while received < 1000 {
+            if receiver.try_recv().is_some() {
+                received += 1;
+            }
+        }
+        assert!(writer_thread.join().is_ok());
+        assert_eq!(receiver.try_recv(), None);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_stats() {
+        let (mut write, mut read) = spsc::<i32>(4);
+        for i in 0..3 {
+            write.try_send(i).unwrap();
+        }
+        read.try_recv();
+        read.try_recv();
+        for i in 0..4 {
+            let _ = write.try_send(i);
+        }
+        assert_eq!(read.try_recv(), Some(2));
+        assert_eq!(
+            write.stats(),
+            QueueStats {
+                sent: 6,
+                received: 3,
+                no_space_left: 1,
+                high_water_mark: 4,
+            }
+        );
+        assert_eq!(read.stats(), write.stats());
+
+        // The zero-sized counter is instrumented as well.
+        let (mut write, mut read) = spsc::<()>(2);
+        for _ in 0..3 {
+            let _ = write.try_send(());
+        }
+        read.try_recv();
+        assert_eq!(read.stats().no_space_left, 1);
+        assert_eq!(read.stats().received, 1);
+    }
+
+    #[test]
+    fn test_closed() {
+        let (mut write, mut read) = spsc::<i32>(4);
+        write.try_send(1).unwrap();
+        assert!(!read.is_closed());
+        drop(write);
+        assert!(read.is_closed());
+        assert_eq!(read.try_recv(), Some(1));
+        assert_eq!(read.try_recv(), None);
+        assert!(read.is_closed());
+
+        let (mut write, mut read) = spsc::<()>(4);
+        write.try_send(()).unwrap();
+        drop(write);
+        assert!(read.is_closed());
+        assert_eq!(read.try_recv(), Some(()));
+        assert_eq!(read.try_recv(), None);
+    }
+
+    #[test]
+    fn test_closed_threaded() {
+        let (mut write, mut read) = spsc::<usize>(4);
+        let writer_thread = std::thread::spawn(move || {
+            let mut sent = 0;
+            while sent < 1000 {
+                if write.try_send(sent).is_ok() {
+                    sent += 1;
+                }
+            }
+        });
+        let mut received = 0;
+        loop {
+            // Check for closing first, so no item sent before can be missed.
+            let closed = read.is_closed();
+            match read.try_recv() {
+                Some(val) => {
+                    assert_eq!(val, received);
+                    received += 1;
+                }
+                None if closed => break,
+                None => {}
+            }
+        }
+        assert_eq!(received, 1000);
+        assert!(writer_thread.join().is_ok());
+    }
+
     #[test]
     fn test_drop_one_side() {
         let (mut write, read) = spsc::<i32>(4);
@@ -0,0 +1,148 @@
+//! Instrumentation counters of the [spsc](crate::spsc) queue and the [triple_buffer](crate::triple_buffer),
+//! which are enabled by the `stats` feature.
+//!
+//! Every counter has a single writing side, so recording it is a plain load and store.
+//! A snapshot can be taken from any handle, e.g. to find undersized queues before they drop data.
+//!
+//! # Example
+//! ```rust
+//! use waitfree_sync::spsc;
+//!
+//! let (mut tx, mut rx) = spsc::spsc(2);
+//! for i in 0..3 {
+//!     let _ = tx.try_send(i);
+//! }
+//! rx.try_recv();
+//!
+//! let stats = rx.stats();
+//! assert_eq!(stats.sent, 2);
+//! assert_eq!(stats.received, 1);
+//! assert_eq!(stats.no_space_left, 1);
+//! assert_eq!(stats.high_water_mark, 2);
+//! ```
+
+use crate::import::{AtomicUsize, Ordering};
+use crossbeam_utils::CachePadded;
+
+/// A snapshot of the counters of a queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// The total number of sent items.
+    pub sent: usize,
+    /// The total number of received items.
+    pub received: usize,
+    /// The number of times a [NoSpaceLeftError](crate::spsc::NoSpaceLeftError) was returned.
+    pub no_space_left: usize,
+    /// The maximum number of pending items observed by the sender.
+    pub high_water_mark: usize,
+}
+
+/// A snapshot of the counters of a latest-value buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    /// The total number of published values.
+    pub published: usize,
+    /// The number of published values taken over by the reader.
+    pub consumed: usize,
+    /// The number of published values which were overwritten before the reader took them over.
+    pub skipped: usize,
+}
+
+/// A counter which is only written by one side.
+#[derive(Debug, Default)]
+struct Counter(AtomicUsize);
+
+impl Counter {
+    #[inline]
+    fn add(&self, value: usize) {
+        // There is only one writer, so a read-modify-write operation is not needed.
+        let current = self.0.load(Ordering::Relaxed);
+        self.0.store(current.wrapping_add(value), Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn max(&self, value: usize) {
+        if value > self.0.load(Ordering::Relaxed) {
+            self.0.store(value, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct QueueCounters {
+    // Written by the sender.
+    sent: Counter,
+    no_space_left: Counter,
+    high_water_mark: Counter,
+    // Written by the receiver.
+    received: CachePadded<Counter>,
+}
+
+impl QueueCounters {
+    /// Records a send attempt. Must only be called by the sender.
+    #[inline]
+    pub(crate) fn record_send(&self, success: bool, capacity: usize) {
+        if success {
+            self.sent.add(1);
+            // The receiver may have freed slots without counting them yet.
+            let depth = self.sent.get().wrapping_sub(self.received.get());
+            self.high_water_mark.max(depth.min(capacity));
+        } else {
+            self.no_space_left.add(1);
+        }
+    }
+
+    /// Records a received item. Must only be called by the receiver.
+    #[inline]
+    pub(crate) fn record_recv(&self) {
+        self.received.add(1);
+    }
+
+    pub(crate) fn snapshot(&self) -> QueueStats {
+        QueueStats {
+            sent: self.sent.get(),
+            received: self.received.get(),
+            no_space_left: self.no_space_left.get(),
+            high_water_mark: self.high_water_mark.get(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BufferCounters {
+    // Written by the writer.
+    published: Counter,
+    skipped: Counter,
+    // Written by the reader.
+    consumed: CachePadded<Counter>,
+}
+
+impl BufferCounters {
+    /// Records a publication. Must only be called by the writer.
+    #[inline]
+    pub(crate) fn record_publish(&self, skipped: bool) {
+        self.published.add(1);
+        if skipped {
+            self.skipped.add(1);
+        }
+    }
+
+    /// Records a value taken over by the reader. Must only be called by the reader.
+    #[inline]
+    pub(crate) fn record_consume(&self) {
+        self.consumed.add(1);
+    }
+
+    pub(crate) fn snapshot(&self) -> BufferStats {
+        BufferStats {
+            published: self.published.get(),
+            consumed: self.consumed.get(),
+            skipped: self.skipped.get(),
+        }
+    }
+}
@@ -12,7 +12,9 @@
 //!
 //!
 
-use crate::import::{Arc, AtomicUsize, Ordering, UnsafeCell};
+use crate::import::{Arc, AtomicU64, AtomicUsize, Ordering, UnsafeCell};
+#[cfg(feature = "stats")]
+use crate::stats::{BufferCounters, BufferStats};
 use crossbeam_utils::CachePadded;
 
 const NEW_DATA_FLAG: usize = 0b100;
@@ -22,6 +24,8 @@ const INDEX_MASK: usize = 0b011;
 struct Shared<T: Sized> {
     mem: [UnsafeCell<Option<T>>; 3],
     latest_free: CachePadded<AtomicUsize>,
+    #[cfg(feature = "stats")]
+    stats: CachePadded<BufferCounters>,
 }
 
 impl<T> Shared<T> {
@@ -33,6 +37,24 @@ impl<T> Shared<T> {
                 UnsafeCell::new(None),
             ],
             latest_free: CachePadded::new(0.into()),
+            #[cfg(feature = "stats")]
+            stats: CachePadded::default(),
+        }
+    }
+
+    fn with_value(initial: T) -> Self
+    where
+        T: Clone,
+    {
+        Shared {
+            mem: [
+                UnsafeCell::new(Some(initial.clone())),
+                UnsafeCell::new(Some(initial.clone())),
+                UnsafeCell::new(Some(initial)),
+            ],
+            latest_free: CachePadded::new(0.into()),
+            #[cfg(feature = "stats")]
+            stats: CachePadded::default(),
         }
     }
 }
@@ -53,6 +75,27 @@ pub fn triple_buffer<T>() -> (Writer<T>, Reader<T>) {
     (w, r)
 }
 
+/// Create a new wait-free triple buffer which is initialized with a value.
+/// In contrast to [triple_buffer], the reader always has a value available.
+/// # Example
+/// ```rust
+/// use waitfree_sync::triple_buffer;
+///
+/// let (mut wr, mut rd) = triple_buffer::triple_buffer_with(0u64);
+/// assert_eq!(rd.read(), 0);
+/// wr.write(42);
+/// assert_eq!(rd.read(), 42);
+/// ```
+pub fn triple_buffer_with<T: Clone>(initial: T) -> (Writer<T>, InitializedReader<T>) {
+    let chan = Arc::new(Shared::with_value(initial));
+
+    let mut w = Writer::new(chan.clone());
+    // All slots are filled, so the writer can read back the initial value.
+    w.last_written = Some(0);
+    let r = InitializedReader(Reader::new(chan));
+    (w, r)
+}
+
 /// The reading side of the [triple_buffer].
 #[derive(Debug)]
 pub struct Reader<T> {
@@ -70,13 +113,9 @@ impl<T> Reader<T> {
         }
     }
 
-    /// Reads the latest available value.
-    /// Returns [None] if the [Writer] has not written anything yet.
+    /// Takes over the latest published slot if the [Writer] has written something new.
     #[inline]
-    pub fn try_read(&mut self) -> Option<T>
-    where
-        T: Clone,
-    {
+    fn update(&mut self) {
         let has_new_data = self.shared.latest_free.load(Ordering::Acquire) & NEW_DATA_FLAG > 0;
         if has_new_data {
             self.read_idx = self
@@ -84,7 +123,44 @@ impl<T> Reader<T> {
                 .latest_free
                 .swap(self.read_idx, Ordering::AcqRel)
                 & INDEX_MASK;
+            #[cfg(feature = "stats")]
+            self.shared.stats.record_consume();
         }
+    }
+
+    /// Returns a snapshot of the counters of the buffer.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> BufferStats {
+        self.shared.stats.snapshot()
+    }
+
+    /// Takes the latest available value out of the buffer, so it is returned only once.
+    /// Returns [None] if there is no value which has not been taken yet.
+    /// It must not be combined with [Writer::try_read], which reads the latest value concurrently.
+    #[inline]
+    pub(crate) fn take(&mut self) -> Option<T> {
+        self.update();
+
+        #[cfg(loom)]
+        let val = unsafe {
+            self.shared.mem[self.read_idx]
+                .get_mut()
+                .with(|ptr| (*ptr).take())
+        };
+        #[cfg(not(loom))]
+        let val = unsafe { (*self.shared.mem[self.read_idx].get()).take() };
+        val
+    }
+
+    /// Reads the latest available value.
+    /// Returns [None] if the [Writer] has not written anything yet.
+    #[inline]
+    pub fn try_read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.update();
 
         #[cfg(loom)]
         let val = unsafe { self.shared.mem[self.read_idx].get().deref() }.clone();
@@ -94,6 +170,46 @@ impl<T> Reader<T> {
     }
 }
 
+/// The reading side of the [triple_buffer_with].
+/// Since the buffer is initialized, there is always a value to read.
+#[derive(Debug)]
+pub struct InitializedReader<T>(Reader<T>);
+
+impl<T> InitializedReader<T> {
+    /// Reads the latest available value.
+    #[inline]
+    pub fn read(&mut self) -> T
+    where
+        T: Clone,
+    {
+        match self.0.try_read() {
+            Some(val) => val,
+            // SAFETY: All slots are filled on creation and the writer only writes `Some`.
+            None => unsafe { core::hint::unreachable_unchecked() },
+        }
+    }
+
+    /// Returns a reference to the latest available value.
+    #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
+    #[inline]
+    pub fn read_ref(&mut self) -> &T {
+        self.0.update();
+        let val = unsafe { &*self.0.shared.mem[self.0.read_idx].get() };
+        match val {
+            Some(val) => val,
+            // SAFETY: All slots are filled on creation and the writer only writes `Some`.
+            None => unsafe { core::hint::unreachable_unchecked() },
+        }
+    }
+
+    /// Returns a snapshot of the counters of the buffer.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> BufferStats {
+        self.0.stats()
+    }
+}
+
 /// The writing side of the [triple_buffer].
 #[derive(Debug)]
 pub struct Writer<T> {
@@ -132,17 +248,22 @@ impl<T> Writer<T> {
     /// Writes an new value.
     #[inline]
     pub fn write(&mut self, data: T) {
+        // Drop old value
+        let _ = self.replace(data);
+    }
+
+    /// Writes an new value and returns the old value of the reused slot instead of dropping it.
+    /// This allows to drop the old value in another thread, e.g. with the [garbage](crate::garbage) module.
+    #[inline]
+    pub fn replace(&mut self, data: T) -> Option<T> {
         #[cfg(loom)]
-        unsafe {
+        let old = unsafe {
             self.shared.mem[self.write_idx & INDEX_MASK]
                 .get_mut()
-                .with(|ptr| {
-                    let _ = ptr.replace(Some(data));
-                });
-        }
+                .with(|ptr| ptr.replace(Some(data)))
+        };
         #[cfg(not(loom))]
-        // Drop old value and write new one
-        let _ = unsafe {
+        let old = unsafe {
             self.shared.mem[self.write_idx & INDEX_MASK]
                 .get()
                 .replace(Some(data))
@@ -154,6 +275,384 @@ impl<T> Writer<T> {
             .shared
             .latest_free
             .swap(self.write_idx | NEW_DATA_FLAG, Ordering::AcqRel);
+        // The previous publication is still flagged if the reader has not taken it over.
+        #[cfg(feature = "stats")]
+        self.shared
+            .stats
+            .record_publish(self.write_idx & NEW_DATA_FLAG > 0);
+        old
+    }
+
+    /// Returns a snapshot of the counters of the buffer.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> BufferStats {
+        self.shared.stats.snapshot()
+    }
+}
+
+// The commit of a slot which has no value yet.
+const EMPTY: u64 = u64::MAX;
+// The commit of a slot which is staged for the next commit.
+const STAGED: u64 = u64::MAX - 1;
+const NEW_COMMIT_FLAG: u64 = 1 << 63;
+
+/// Create a table of `N` triple buffers in one heap allocation, e.g. for hundreds of signals.
+///
+/// Every [write](TableWriter::write) is published on its own. Several cells can also be [staged](TableWriter::stage)
+/// and published together with one [commit](TableWriter::commit), so the reader never sees a mix of old and new cells.
+/// Every slot is tagged with the commit which wrote it, and only the number of the latest commit is swapped.
+/// The cells are not padded to cache lines, so neighbouring cells share them.
+/// # Example
+/// ```rust
+/// use waitfree_sync::triple_buffer;
+///
+/// let (mut wr, mut rd) = triple_buffer::table::<u64, 4>();
+/// wr.write(2, 42);
+/// assert_eq!(rd.try_read(2), Some(42));
+/// assert_eq!(rd.try_read(3), None);
+/// ```
+pub fn table<T, const N: usize>() -> (TableWriter<T, N>, TableReader<T, N>) {
+    // The cells are created one after another in the boxed slice, so the table is never on the stack.
+    let chan = Arc::new(Table {
+        cells: (0..N)
+            .map(|_| TableCell {
+                mem: [
+                    UnsafeCell::new(None),
+                    UnsafeCell::new(None),
+                    UnsafeCell::new(None),
+                ],
+                commits: [
+                    AtomicU64::new(EMPTY),
+                    AtomicU64::new(EMPTY),
+                    AtomicU64::new(EMPTY),
+                ],
+                dirty: AtomicU64::new(0),
+            })
+            .collect(),
+        latest_commit: CachePadded::new(AtomicU64::new(0)),
+    });
+
+    let w = TableWriter {
+        shared: chan.clone(),
+        commit: 0,
+        reader_commit: 0,
+        staged: Vec::with_capacity(N),
+    };
+    let r = TableReader {
+        shared: chan,
+        commit: 0,
+    };
+    (w, r)
+}
+
+#[derive(Debug)]
+struct TableCell<T> {
+    mem: [UnsafeCell<Option<T>>; 3],
+    // The commit which wrote the slot, [STAGED] or [EMPTY].
+    // They only need relaxed ordering, since the values are synchronized by the latest commit.
+    commits: [AtomicU64; 3],
+    // The dirty bits of the cells `idx..idx + 64`, if `idx` is a multiple of 64. They are set on every commit
+    // and cleared by a scan of the reader. Storing them in the cells keeps the table in one allocation.
+    dirty: AtomicU64,
+}
+
+impl<T> TableCell<T> {
+    /// Returns the slot with the value of the cell as of the given commit.
+    #[inline]
+    fn slot(&self, commit: u64) -> Option<usize> {
+        let mut latest: Option<(usize, u64)> = None;
+        for (slot, slot_commit) in self.commits.iter().enumerate() {
+            let slot_commit = slot_commit.load(Ordering::Relaxed);
+            if slot_commit <= commit && latest.map_or(true, |(_, latest)| slot_commit > latest) {
+                latest = Some((slot, slot_commit));
+            }
+        }
+        latest.map(|(slot, _)| slot)
+    }
+
+    /// Returns the staged slot.
+    #[inline]
+    fn staged(&self) -> Option<usize> {
+        self.commits
+            .iter()
+            .position(|commit| commit.load(Ordering::Relaxed) == STAGED)
+    }
+}
+
+#[derive(Debug)]
+struct Table<T> {
+    cells: Box<[TableCell<T>]>,
+    latest_commit: CachePadded<AtomicU64>,
+}
+
+impl<T> Table<T> {
+    /// Returns the word with the dirty bit of the cell `idx`.
+    #[inline]
+    fn dirty_word(&self, idx: usize) -> &AtomicU64 {
+        &self.cells[idx & !63].dirty
+    }
+}
+
+/// The reading side of the [table].
+#[derive(Debug)]
+pub struct TableReader<T, const N: usize> {
+    shared: Arc<Table<T>>,
+    commit: u64,
+}
+unsafe impl<T: Send, const N: usize> Send for TableReader<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for TableReader<T, N> {}
+
+impl<T, const N: usize> TableReader<T, N> {
+    /// Takes over the latest commit if the [TableWriter] has committed something new.
+    #[inline]
+    fn update(&mut self) {
+        if self.shared.latest_commit.load(Ordering::Acquire) & NEW_COMMIT_FLAG > 0 {
+            // Keep the commit in the shared word, so the writer sees that it was taken over.
+            self.commit = self
+                .shared
+                .latest_commit
+                .fetch_and(!NEW_COMMIT_FLAG, Ordering::AcqRel)
+                & !NEW_COMMIT_FLAG;
+        }
+    }
+
+    /// Reads the value of the cell `idx` as of the commit of the reader.
+    #[inline]
+    fn read(&self, idx: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        let cell = &self.shared.cells[idx];
+        let slot = cell.slot(self.commit)?;
+
+        #[cfg(loom)]
+        let val = unsafe { cell.mem[slot].get().deref() }.clone();
+        #[cfg(not(loom))]
+        let val = unsafe { &*cell.mem[slot].get() }.clone();
+        val
+    }
+
+    /// Reads the latest available value of the cell `idx`.
+    /// Returns [None] if the [TableWriter] has not written the cell yet.
+    /// # Panic
+    /// Panics if `idx` is not below `N`.
+    #[inline]
+    pub fn try_read(&mut self, idx: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.update();
+        self.read(idx)
+    }
+
+    /// Takes over the latest commit and returns a consistent view of all cells,
+    /// which does not change while it is borrowed.
+    #[inline]
+    pub fn snapshot(&mut self) -> TableSnapshot<'_, T, N> {
+        self.update();
+        TableSnapshot { reader: self }
+    }
+
+    /// Returns an iterator over the cells which were written since the last scan, together with their latest value.
+    ///
+    /// The values are read from one snapshot. A cell which is committed during the scan is returned again by the next scan.
+    pub fn dirty(&mut self) -> Dirty<'_, T, N>
+    where
+        T: Clone,
+    {
+        self.update();
+        Dirty {
+            reader: self,
+            word: 0,
+            bits: 0,
+        }
+    }
+}
+
+/// A consistent view of all cells of a [table]. It is created by [TableReader::snapshot].
+#[derive(Debug)]
+pub struct TableSnapshot<'a, T, const N: usize> {
+    reader: &'a TableReader<T, N>,
+}
+
+impl<T, const N: usize> TableSnapshot<'_, T, N> {
+    /// Reads the value of the cell `idx`.
+    /// Returns [None] if the [TableWriter] had not written the cell.
+    /// # Panic
+    /// Panics if `idx` is not below `N`.
+    #[inline]
+    pub fn try_read(&self, idx: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.reader.read(idx)
+    }
+
+    /// Returns a reference to the value of the cell `idx`.
+    /// Returns [None] if the [TableWriter] had not written the cell.
+    /// # Panic
+    /// Panics if `idx` is not below `N`.
+    #[cfg(not(loom))] // We can't return a reference to an UnsafeCell of loom.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        let cell = &self.reader.shared.cells[idx];
+        let slot = cell.slot(self.reader.commit)?;
+        unsafe { &*cell.mem[slot].get() }.as_ref()
+    }
+}
+
+/// An iterator over the cells of a [table] which were written since the last scan.
+/// It is created by [TableReader::dirty].
+#[derive(Debug)]
+pub struct Dirty<'a, T, const N: usize> {
+    reader: &'a mut TableReader<T, N>,
+    // The index of the first cell of the next word of dirty bits, which is cleared when it is reached.
+    word: usize,
+    bits: u64,
+}
+
+impl<T: Clone, const N: usize> Iterator for Dirty<'_, T, N> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<(usize, T)> {
+        loop {
+            while self.bits == 0 {
+                let cell = self.reader.shared.cells.get(self.word)?;
+                self.bits = cell.dirty.swap(0, Ordering::AcqRel);
+                self.word += 64;
+            }
+            let idx = self.word - 64 + self.bits.trailing_zeros() as usize;
+            self.bits &= self.bits - 1;
+
+            // A cell which was committed after the snapshot stays dirty for the next scan.
+            let newer = self.reader.shared.cells[idx].commits.iter().any(|commit| {
+                let commit = commit.load(Ordering::Relaxed);
+                commit > self.reader.commit && commit < STAGED
+            });
+            if newer {
+                self.reader
+                    .shared
+                    .dirty_word(idx)
+                    .fetch_or(1 << (idx % 64), Ordering::Release);
+            }
+            if let Some(val) = self.reader.read(idx) {
+                return Some((idx, val));
+            }
+        }
+    }
+}
+
+/// The writing side of the [table].
+#[derive(Debug)]
+pub struct TableWriter<T, const N: usize> {
+    shared: Arc<Table<T>>,
+    // The latest commit, which is published in the shared word.
+    commit: u64,
+    // The commit which the reader took over last, as far as the writer knows.
+    reader_commit: u64,
+    // The cells with a staged slot. It never grows beyond `N`, so it does not allocate.
+    staged: Vec<usize>,
+}
+unsafe impl<T: Send, const N: usize> Send for TableWriter<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for TableWriter<T, N> {}
+
+impl<T, const N: usize> TableWriter<T, N> {
+    /// Writes a new value into the cell `idx` and publishes it together with all staged cells.
+    /// # Panic
+    /// Panics if `idx` is not below `N`.
+    #[inline]
+    pub fn write(&mut self, idx: usize, data: T) {
+        // Drop old value
+        let _ = self.replace(idx, data);
+    }
+
+    /// Writes a new value into the cell `idx` and returns the old value of the reused slot instead of dropping it.
+    /// The value is published together with all staged cells.
+    /// # Panic
+    /// Panics if `idx` is not below `N`.
+    #[inline]
+    pub fn replace(&mut self, idx: usize, data: T) -> Option<T> {
+        let old = self.stage(idx, data);
+        self.commit();
+        old
+    }
+
+    /// Stages a new value for the cell `idx`, which is published by the next [commit](TableWriter::commit).
+    /// Staging a cell again replaces the staged value.
+    /// Returns the old value of the reused slot instead of dropping it.
+    /// # Panic
+    /// Panics if `idx` is not below `N`.
+    /// # Example
+    /// ```rust
+    /// use waitfree_sync::triple_buffer;
+    ///
+    /// const POSE: usize = 0;
+    /// const VELOCITY: usize = 1;
+    ///
+    /// let (mut wr, mut rd) = triple_buffer::table::<f64, 2>();
+    /// wr.stage(POSE, 1.0);
+    /// wr.stage(VELOCITY, 0.5);
+    /// assert_eq!(rd.try_read(POSE), None);
+    /// wr.commit();
+    ///
+    /// let snapshot = rd.snapshot();
+    /// assert_eq!(snapshot.try_read(POSE), Some(1.0));
+    /// assert_eq!(snapshot.try_read(VELOCITY), Some(0.5));
+    /// ```
+    #[inline]
+    pub fn stage(&mut self, idx: usize, data: T) -> Option<T> {
+        let cell = &self.shared.cells[idx];
+        let slot = match cell.staged() {
+            Some(slot) => slot,
+            None => {
+                // The reader holds either the published commit or the one it took over before.
+                let published = cell.slot(self.commit);
+                let read = cell.slot(self.reader_commit);
+                let mut slot = 0;
+                while Some(slot) == published || Some(slot) == read {
+                    slot += 1;
+                }
+                cell.commits[slot].store(STAGED, Ordering::Relaxed);
+                self.staged.push(idx);
+                slot
+            }
+        };
+
+        #[cfg(loom)]
+        let old = unsafe { cell.mem[slot].get_mut().with(|ptr| ptr.replace(Some(data))) };
+        #[cfg(not(loom))]
+        let old = unsafe { cell.mem[slot].get().replace(Some(data)) };
+        old
+    }
+
+    /// Publishes all staged cells as one commit, so the [TableReader] sees either all or none of them.
+    #[inline]
+    pub fn commit(&mut self) {
+        if self.staged.is_empty() {
+            return;
+        }
+        self.commit += 1;
+        for idx in self.staged.iter() {
+            let cell = &self.shared.cells[*idx];
+            if let Some(slot) = cell.staged() {
+                cell.commits[slot].store(self.commit, Ordering::Relaxed);
+            }
+        }
+
+        let previous = self
+            .shared
+            .latest_commit
+            .swap(self.commit | NEW_COMMIT_FLAG, Ordering::AcqRel);
+        if previous & NEW_COMMIT_FLAG == 0 {
+            // The reader took over the previous commit.
+            self.reader_commit = previous;
+        }
+        for idx in self.staged.drain(..) {
+            self.shared
+                .dirty_word(idx)
+                .fetch_or(1 << (idx % 64), Ordering::Release);
+        }
     }
 }
 
@@ -177,4 +676,160 @@ mod test {
         w.write(vec![0; 15]);
         assert_eq!(r.try_read(), Some(vec![0; 15]));
     }
+
+    #[test]
+    fn test_replace() {
+        let (mut w, mut r) = triple_buffer();
+        assert_eq!(w.replace(1), None);
+        assert_eq!(w.replace(2), None);
+        assert_eq!(r.try_read(), Some(2));
+        // The slot of the first value was free again.
+        assert_eq!(w.replace(3), Some(1));
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_stats() {
+        let (mut w, mut r) = triple_buffer();
+        r.try_read();
+        w.write(1);
+        w.write(2);
+        r.try_read();
+        w.write(3);
+        w.write(4);
+        w.write(5);
+        assert_eq!(
+            w.stats(),
+            BufferStats {
+                published: 5,
+                consumed: 1,
+                skipped: 3,
+            }
+        );
+        r.try_read();
+        assert_eq!(r.stats().consumed, 2);
+    }
+
+    #[test]
+    fn test_table() {
+        let (mut w, mut r) = table::<Vec<u8>, 100>();
+        assert_eq!(r.try_read(0), None);
+        assert_eq!(r.dirty().next(), None);
+
+        w.write(70, vec![1]);
+        w.write(3, vec![2]);
+        w.write(70, vec![3]);
+        assert_eq!(w.replace(99, vec![4]), None);
+        assert_eq!(r.try_read(3), Some(vec![2]));
+        assert_eq!(
+            r.dirty().collect::<Vec<_>>(),
+            vec![(3, vec![2]), (70, vec![3]), (99, vec![4])]
+        );
+        assert_eq!(r.dirty().next(), None);
+        // The values stay readable.
+        assert_eq!(r.try_read(70), Some(vec![3]));
+
+        w.write(0, vec![5]);
+        assert_eq!(r.dirty().collect::<Vec<_>>(), vec![(0, vec![5])]);
+    }
+
+    #[cfg(not(loom))]
+    #[test]
+    fn test_commit() {
+        let (mut w, mut r) = table::<Vec<u8>, 3>();
+        assert_eq!(w.stage(0, vec![1]), None);
+        assert_eq!(w.stage(2, vec![2]), None);
+        // Staging a cell again replaces the staged value.
+        assert_eq!(w.stage(0, vec![3]), Some(vec![1]));
+        assert_eq!(r.try_read(0), None);
+        assert_eq!(r.dirty().next(), None);
+
+        w.commit();
+        let snapshot = r.snapshot();
+        assert_eq!(snapshot.try_read(0), Some(vec![3]));
+        assert_eq!(snapshot.get(2), Some(&vec![2]));
+        assert_eq!(snapshot.get(1), None);
+
+        // The snapshot of the reader protects its slots, while the writer keeps committing.
+        let snapshot = r.snapshot();
+        for i in 4..10 {
+            w.stage(0, vec![i]);
+            w.stage(2, vec![i]);
+            w.commit();
+        }
+        assert_eq!(snapshot.try_read(0), Some(vec![3]));
+        assert_eq!(snapshot.try_read(2), Some(vec![2]));
+        assert_eq!(
+            r.dirty().collect::<Vec<_>>(),
+            vec![(0, vec![9]), (2, vec![9])]
+        );
+    }
+
+    #[test]
+    fn test_commit_dirty() {
+        let (mut w, mut r) = table::<u8, 2>();
+        w.write(0, 1);
+        let mut dirty = r.dirty();
+        // A commit during the scan is reported by the next scan.
+        w.write(0, 2);
+        assert_eq!(dirty.next(), Some((0, 1)));
+        assert_eq!(dirty.next(), None);
+        assert_eq!(r.dirty().collect::<Vec<_>>(), vec![(0, 2)]);
+        assert_eq!(r.dirty().next(), None);
+    }
+
+    #[cfg(not(loom))]
+    #[test]
+    fn test_commit_threaded() {
+        let (mut w, mut r) = table::<u64, 4>();
+        let reader_thread = std::thread::spawn(move || {
+            let mut last = 0;
+            while last < 1000 {
+                let snapshot = r.snapshot();
+                if let Some(first) = snapshot.try_read(0) {
+                    // The cells are never mixed from different commits.
+                    for idx in 1..4 {
+                        assert_eq!(snapshot.try_read(idx), Some(first * idx as u64));
+                    }
+                    assert!(first >= last);
+                    last = first;
+                }
+            }
+        });
+        for i in 1..=1000 {
+            for idx in 0..4 {
+                w.stage(idx, i * idx as u64);
+            }
+            w.stage(0, i);
+            w.commit();
+        }
+        assert!(reader_thread.join().is_ok());
+    }
+
+    #[test]
+    fn test_large_table() {
+        // The table would not fit on the stack of the test thread.
+        let (mut w, mut r) = table::<[u8; 4096], 1024>();
+        w.write(1000, [1; 4096]);
+        assert_eq!(r.try_read(1000), Some([1; 4096]));
+        assert_eq!(
+            r.dirty().map(|(idx, _)| idx).collect::<Vec<_>>(),
+            vec![1000]
+        );
+    }
+
+    #[cfg(not(loom))]
+    #[test]
+    fn test_initialized() {
+        let (mut w, mut r) = triple_buffer_with(vec![1; 3]);
+        assert_eq!(w.try_read(), Some(vec![1; 3]));
+        assert_eq!(r.read(), vec![1; 3]);
+        assert_eq!(r.read_ref(), &vec![1; 3]);
+        w.write(vec![2; 3]);
+        assert_eq!(r.read_ref(), &vec![2; 3]);
+        w.write(vec![3; 3]);
+        w.write(vec![4; 3]);
+        assert_eq!(r.read(), vec![4; 3]);
+        assert_eq!(r.read(), vec![4; 3]);
+    }
 }
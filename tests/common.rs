@@ -19,6 +19,16 @@ impl<T> ReadPrimitive<T> for triple_buffer::Reader<T> {
     }
 }
 
+impl<T> ReadPrimitive<T> for triple_buffer::InitializedReader<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        Some(self.read())
+    }
+}
+
 impl<T> WritePrimitive<T, ()> for triple_buffer::Writer<T> {
     fn write(&mut self, data: T) -> Result<(), ()> {
         self.write(data);
@@ -41,3 +51,101 @@ impl<T> WritePrimitive<T, NoSpaceLeftError<T>> for spsc::Sender<T> {
         self.try_send(data)
     }
 }
+
+impl<T> ReadPrimitive<T> for broadcast_buffer::Reader<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_read()
+    }
+}
+
+impl<T> WritePrimitive<T, ()> for broadcast_buffer::Writer<T> {
+    fn write(&mut self, data: T) -> Result<(), ()> {
+        self.write(data);
+        Ok(())
+    }
+}
+
+impl<T> ReadPrimitive<T> for register::Reader<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_read()
+    }
+}
+
+impl<T> WritePrimitive<T, ()> for register::Writer<T> {
+    fn write(&mut self, data: T) -> Result<(), ()> {
+        self.write(data);
+        Ok(())
+    }
+}
+
+impl<T> ReadPrimitive<T> for mpsc::Receiver<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_recv()
+    }
+}
+
+impl<T> WritePrimitive<T, NoSpaceLeftError<T>> for mpsc::Sender<T> {
+    fn write(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.try_send(data)
+    }
+}
+
+impl<T> ReadPrimitive<T> for spmc::Receiver<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_recv()
+    }
+}
+
+impl<T> WritePrimitive<T, NoSpaceLeftError<T>> for spmc::Sender<T> {
+    fn write(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.try_send(data)
+    }
+}
+
+impl<T> ReadPrimitive<T> for mpmc::Receiver<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_recv()
+    }
+}
+
+impl<T> WritePrimitive<T, NoSpaceLeftError<T>> for mpmc::Sender<T> {
+    fn write(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.try_send(data)
+    }
+}
+
+impl<T> ReadPrimitive<T> for broadcast_ring::Receiver<T> {
+    #[inline]
+    fn read(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_recv().ok().flatten()
+    }
+}
+
+impl<T> WritePrimitive<T, NoSpaceLeftError<T>> for broadcast_ring::Sender<T> {
+    fn write(&mut self, data: T) -> Result<(), NoSpaceLeftError<T>> {
+        self.try_send(data)
+    }
+}
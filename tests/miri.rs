@@ -4,6 +4,12 @@ use loom::thread;
 use std::fmt::Debug;
 #[cfg(not(loom))]
 use std::thread;
+use waitfree_sync::broadcast_buffer;
+use waitfree_sync::broadcast_ring::{self, Overflow};
+use waitfree_sync::mpmc;
+use waitfree_sync::mpsc;
+use waitfree_sync::register;
+use waitfree_sync::spmc;
 use waitfree_sync::spsc;
 use waitfree_sync::triple_buffer;
 
@@ -127,6 +133,56 @@ fn test_tripple_buffer() {
     test_heapdata_multithread(triple_buffer::triple_buffer());
 }
 
+#[cfg(not(loom))]
+#[test]
+fn test_tripple_buffer_with() {
+    test_multithread(triple_buffer::triple_buffer_with([0; 50]));
+    test_heapdata(triple_buffer::triple_buffer_with(SomeStruct::default()));
+    test_heapdata_multithread(triple_buffer::triple_buffer_with(SomeStruct::default()));
+}
+
+fn broadcast_buffer_pair<T>() -> (broadcast_buffer::Writer<T>, broadcast_buffer::Reader<T>) {
+    let (w, mut r) = broadcast_buffer::broadcast_buffer(2);
+    (w, r.pop().unwrap())
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_broadcast_buffer() {
+    test_multithread(broadcast_buffer_pair());
+    test_heapdata(broadcast_buffer_pair());
+    test_heapdata_multithread(broadcast_buffer_pair());
+}
+
+fn register_pair<T>() -> (register::Writer<T>, register::Reader<T>) {
+    let (mut w, r) = register::register(2);
+    (w.pop().unwrap(), r)
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_register() {
+    test_multithread(register_pair());
+    test_heapdata(register_pair());
+    test_heapdata_multithread(register_pair());
+}
+
+fn broadcast_ring_pair<T>(
+    overflow: Overflow,
+) -> (broadcast_ring::Sender<T>, broadcast_ring::Receiver<T>) {
+    let (w, mut r) = broadcast_ring::broadcast_ring(COUNT, 1, overflow);
+    (w, r.pop().unwrap())
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_broadcast_ring() {
+    test_multithread(broadcast_ring_pair(Overflow::Reject));
+    test_heapdata(broadcast_ring_pair(Overflow::Reject));
+    test_heapdata_multithread(broadcast_ring_pair(Overflow::Reject));
+    test_heapdata(broadcast_ring_pair(Overflow::Overwrite));
+}
+
 #[cfg(not(loom))]
 #[test]
 fn test_spsc() {
@@ -135,6 +191,30 @@ fn test_spsc() {
     test_heapdata_multithread(spsc::spsc(COUNT));
 }
 
+#[cfg(not(loom))]
+#[test]
+fn test_mpsc() {
+    test_multithread(mpsc::mpsc(COUNT));
+    test_heapdata(mpsc::mpsc(COUNT));
+    test_heapdata_multithread(mpsc::mpsc(COUNT));
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_spmc() {
+    test_multithread(spmc::spmc(COUNT));
+    test_heapdata(spmc::spmc(COUNT));
+    test_heapdata_multithread(spmc::spmc(COUNT));
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_mpmc() {
+    test_multithread(mpmc::mpmc(COUNT));
+    test_heapdata(mpmc::mpmc(COUNT));
+    test_heapdata_multithread(mpmc::mpmc(COUNT));
+}
+
 #[test]
 #[cfg(loom)]
 fn loom_tripple_buffer() {
@@ -149,6 +229,38 @@ fn loom_tripple_buffer() {
     loom::model(|| {
         test_heapdata_multithread(triple_buffer::triple_buffer());
     });
+
+    loom::model(|| {
+        test_multithread(triple_buffer::triple_buffer_with([0; 50]));
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_broadcast_buffer() {
+    // The writer scans all readers, so the state space has to be bounded.
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(3);
+    model.check(|| {
+        test_multithread(broadcast_buffer_pair());
+    });
+    model.check(|| {
+        test_heapdata_multithread(broadcast_buffer_pair());
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_register() {
+    loom::model(|| {
+        test_multithread(register_pair());
+    });
+    loom::model(|| {
+        test_heapdata(register_pair());
+    });
+    loom::model(|| {
+        test_heapdata_multithread(register_pair());
+    });
 }
 
 #[test]
@@ -163,4 +275,303 @@ fn loom_spsc() {
     loom::model(|| {
         test_heapdata_multithread(spsc::spsc(COUNT));
     });
+    loom::model(|| {
+        let (mut tx, mut rx) = spsc::spsc::<()>(2);
+        let writer_thread =
+            thread::spawn(move || (0..3).filter(|_| tx.try_send(()).is_ok()).count());
+        let mut received = 0;
+        for _ in 0..3 {
+            received += rx.try_recv().is_some() as usize;
+        }
+        let sent = writer_thread.join().unwrap();
+        while rx.try_recv().is_some() {
+            received += 1;
+        }
+        assert_eq!(received, sent);
+        assert_eq!(rx.try_recv(), None);
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_spsc_closed() {
+    loom::model(|| {
+        let (mut tx, mut rx) = spsc::spsc::<usize>(2);
+        let writer_thread = thread::spawn(move || {
+            tx.try_send(1).unwrap();
+            tx.try_send(2).unwrap();
+        });
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            // Check for closing first, so no item sent before can be missed.
+            let closed = rx.is_closed();
+            match rx.try_recv() {
+                Some(val) => received.push(val),
+                None if closed => assert_eq!(received, vec![1, 2]),
+                None => {}
+            }
+        }
+        assert!(writer_thread.join().is_ok());
+        assert!(rx.is_closed());
+        received.extend(core::iter::from_fn(|| rx.try_recv()));
+        assert_eq!(received, vec![1, 2]);
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_mpsc() {
+    loom::model(|| {
+        test_multithread(mpsc::mpsc(COUNT));
+    });
+    loom::model(|| {
+        test_heapdata(mpsc::mpsc(COUNT));
+    });
+    loom::model(|| {
+        test_heapdata_multithread(mpsc::mpsc(COUNT));
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_spmc() {
+    loom::model(|| {
+        test_multithread(spmc::spmc(COUNT));
+    });
+    loom::model(|| {
+        test_heapdata(spmc::spmc(COUNT));
+    });
+    loom::model(|| {
+        test_heapdata_multithread(spmc::spmc(COUNT));
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_mpmc() {
+    // Both sides help to advance the frontiers, so the state space has to be bounded.
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(3);
+    model.check(|| {
+        test_multithread(mpmc::mpmc(COUNT));
+    });
+    model.check(|| {
+        test_heapdata(mpmc::mpmc(COUNT));
+    });
+    model.check(|| {
+        test_heapdata_multithread(mpmc::mpmc(COUNT));
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_mpmc_multiple() {
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(3);
+    model.check(|| {
+        let (mut w1, mut r1) = mpmc::mpmc::<usize>(2);
+        let (mut w2, mut r2) = (w1.clone(), r1.clone());
+        let writer_thread = thread::spawn(move || w2.try_send(2).is_ok() as usize);
+        let reader_thread = thread::spawn(move || r2.try_recv().into_iter().count());
+        let sent = w1.try_send(1).is_ok() as usize + writer_thread.join().unwrap();
+        let mut received = reader_thread.join().unwrap();
+        while r1.try_recv().is_some() {
+            received += 1;
+        }
+        assert_eq!(sent, received);
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_broadcast_ring() {
+    loom::model(|| {
+        test_multithread(broadcast_ring_pair(Overflow::Reject));
+    });
+    loom::model(|| {
+        test_heapdata_multithread(broadcast_ring_pair(Overflow::Reject));
+    });
+    loom::model(|| {
+        let (mut w, mut r) =
+            broadcast_ring::broadcast_ring::<SomeStruct>(2, 1, Overflow::Overwrite);
+        let mut r = r.pop().unwrap();
+        let reader_thread = thread::spawn(move || {
+            for _ in 0..3 {
+                if let Ok(Some(val)) = r.try_recv() {
+                    assert_eq!(val.inner_field, vec![Some(SomeEnum::State1)]);
+                }
+            }
+        });
+        for i in 0..3 {
+            let _ = w.try_send(SomeStruct {
+                counter: i,
+                inner_field: vec![Some(SomeEnum::State1)],
+            });
+        }
+        assert!(reader_thread.join().is_ok());
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_oneshot() {
+    loom::model(|| {
+        let (w, mut r) = waitfree_sync::oneshot::oneshot();
+        let writer_thread = thread::spawn(move || {
+            w.send(SomeStruct::default()).unwrap();
+        });
+        if let Ok(Some(val)) = r.try_recv() {
+            assert_eq!(val, SomeStruct::default());
+        }
+        assert!(writer_thread.join().is_ok());
+    });
+    loom::model(|| {
+        let (w, r) = waitfree_sync::oneshot::oneshot();
+        let reader_thread = thread::spawn(move || drop(r));
+        let _ = w.send(SomeStruct::default());
+        assert!(reader_thread.join().is_ok());
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_rcu() {
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(3);
+    model.check(|| {
+        let (mut w, mut r) = waitfree_sync::rcu::rcu(std::sync::Arc::new(SomeStruct::default()), 1);
+        let mut r = r.pop().unwrap();
+        let reader_thread = thread::spawn(move || {
+            for _ in 0..COUNT {
+                assert_eq!(r.load_full().inner_field, vec![Some(SomeEnum::State1)]);
+            }
+        });
+        for i in 0..COUNT {
+            w.store(std::sync::Arc::new(SomeStruct {
+                counter: i as i32,
+                inner_field: vec![Some(SomeEnum::State1)],
+            }));
+        }
+        assert!(reader_thread.join().is_ok());
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_conflate() {
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(3);
+    model.check(|| {
+        let (mut tx, mut rx) = waitfree_sync::conflate::conflate(2);
+        let sender_thread = thread::spawn(move || {
+            tx.send(0, 1);
+            tx.send(1, 2);
+            tx.send(0, 3);
+        });
+        let mut last = [0; 2];
+        for _ in 0..2 {
+            if let Some((key, value)) = rx.try_recv() {
+                assert!(value > last[key]);
+                last[key] = value;
+            }
+        }
+        assert!(sender_thread.join().is_ok());
+        while let Some((key, value)) = rx.try_recv() {
+            assert!(value > last[key]);
+            last[key] = value;
+        }
+        // The latest value of every key is received.
+        assert_eq!(last, [3, 2]);
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_triple_buffer_table() {
+    loom::model(|| {
+        let (mut w, mut r) = triple_buffer::table::<usize, 2>();
+        let writer_thread = thread::spawn(move || {
+            w.write(0, 1);
+            w.write(1, 2);
+            w.write(0, 3);
+        });
+        let mut last = [0; 2];
+        for (idx, val) in r.dirty() {
+            assert!(val >= last[idx]);
+            last[idx] = val;
+        }
+        assert!(writer_thread.join().is_ok());
+        for (idx, val) in r.dirty() {
+            assert!(val >= last[idx]);
+            last[idx] = val;
+        }
+        // The latest value of every written cell is reported.
+        assert_eq!(last, [3, 2]);
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_triple_buffer_commit() {
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(3);
+    model.check(|| {
+        let (mut w, mut r) = triple_buffer::table::<usize, 2>();
+        let writer_thread = thread::spawn(move || {
+            for i in 1..4 {
+                w.stage(0, i);
+                w.stage(1, i * 2);
+                w.commit();
+            }
+        });
+        for _ in 0..2 {
+            let snapshot = r.snapshot();
+            if let Some(a) = snapshot.try_read(0) {
+                assert_eq!(snapshot.try_read(1), Some(a * 2));
+            }
+        }
+        assert!(writer_thread.join().is_ok());
+        let snapshot = r.snapshot();
+        assert_eq!(snapshot.try_read(0), Some(3));
+        assert_eq!(snapshot.try_read(1), Some(6));
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn loom_history() {
+    loom::model(|| {
+        let (mut w, mut r) = waitfree_sync::history::history::<usize>(2);
+        let writer_thread = thread::spawn(move || {
+            for i in 0..4 {
+                w.write(i);
+            }
+        });
+        for _ in 0..3 {
+            r.update();
+            if let Some(seq) = r.sequence() {
+                // The history consists of consecutive writes.
+                for i in 0..r.len() {
+                    assert_eq!(r.get_cloned(i), Some(seq as usize - i));
+                }
+            }
+        }
+        assert!(writer_thread.join().is_ok());
+        r.update();
+        assert_eq!(r.get_cloned(0), Some(3));
+        assert_eq!(r.get_cloned(1), Some(2));
+    });
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_garbage_full() {
+    let (mut disposer, mut collector) = waitfree_sync::garbage::garbage(2);
+    disposer.dispose(vec![1]).unwrap();
+    disposer.dispose(vec![2]).unwrap();
+    // The value is handed back instead of being dropped in the real-time thread.
+    let value = disposer.dispose(vec![3]).unwrap_err().into_inner();
+    assert_eq!(value, vec![3]);
+    assert_eq!(collector.collect(), 2);
+    assert!(disposer.dispose(value).is_ok());
 }